@@ -0,0 +1,45 @@
+use hdk::prelude::*;
+use holomessage_integrity::{
+    Attachment, AttachmentChunk, EntryTypes, UploadFileInput, ATTACHMENT_HASH_LENGTH,
+};
+
+#[hdk_extern]
+pub fn upload_chunk(chunk: AttachmentChunk) -> ExternResult<EntryHash> {
+    let chunk_hash = hash_entry(&chunk)?;
+    create_entry(EntryTypes::AttachmentChunk(chunk))?;
+    Ok(chunk_hash)
+}
+
+/// Create an attachment from previously uploaded chunks.
+#[hdk_extern]
+pub fn upload_file(input: UploadFileInput) -> ExternResult<EntryHash> {
+    let mut content = Vec::new();
+    for chunk_hash in input.chunks.iter() {
+        let entry = must_get_entry(chunk_hash.clone())?.into_content();
+        content.extend(AttachmentChunk::try_from(entry)?.data);
+    }
+    let attachment = Attachment {
+        filename: input.filename,
+        mime_type: input.mime_type,
+        size: content.len() as u64,
+        hash: hash_blake2b(content, ATTACHMENT_HASH_LENGTH)?,
+        chunks: input.chunks,
+    };
+    let attachment_hash = hash_entry(&attachment)?;
+    create_entry(EntryTypes::Attachment(attachment))?;
+    Ok(attachment_hash)
+}
+
+#[hdk_extern]
+pub fn download_file(attachment_hash: EntryHash) -> ExternResult<Option<Attachment>> {
+    get(attachment_hash, GetOptions::default())?
+        .map(Attachment::try_from)
+        .transpose()
+}
+
+#[hdk_extern]
+pub fn download_chunk(chunk_hash: EntryHash) -> ExternResult<Option<AttachmentChunk>> {
+    get(chunk_hash, GetOptions::default())?
+        .map(AttachmentChunk::try_from)
+        .transpose()
+}
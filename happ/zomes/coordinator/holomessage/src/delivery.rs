@@ -0,0 +1,62 @@
+use hdk::prelude::*;
+
+use crate::presence::get_online_agents;
+
+/// Online agents asked at once whether they get a message.
+const PEERS_ASKED: usize = 3;
+
+/// Whether another agent online gets a message of this agent from the DHT.
+/// A few random agents are asked at once, so that a check stays cheap in a
+/// large network and no agent is asked by everyone.
+#[hdk_extern]
+pub fn is_message_seen(action_hash: ActionHash) -> ExternResult<bool> {
+    let my_agent_key = agent_info()?.agent_latest_pubkey;
+    let mut peers: Vec<AgentPubKey> = get_online_agents(())?
+        .into_iter()
+        .map(|presence| presence.agent)
+        .filter(|agent| agent != &my_agent_key)
+        .collect();
+    // the first agents of a partial shuffle
+    let random = random_bytes(PEERS_ASKED as u32)?;
+    for (i, byte) in random.iter().enumerate().take(peers.len()) {
+        let j = i + *byte as usize % (peers.len() - i);
+        peers.swap(i, j);
+    }
+    peers.truncate(PEERS_ASKED);
+
+    let zome_name = zome_info()?.name;
+    let payload = ExternIO::encode(action_hash).map_err(|err| wasm_error!(err))?;
+    let calls = peers
+        .into_iter()
+        .map(|agent| {
+            Call::new(
+                CallTarget::NetworkAgent(agent),
+                zome_name.clone(),
+                "holds_message".into(),
+                None,
+                payload.clone(),
+            )
+        })
+        .collect();
+    // the calls run in parallel, offline or unreachable agents result in a
+    // network error response
+    let responses = HDK.with(|hdk| hdk.borrow().call(calls))?;
+    for response in responses {
+        if let ZomeCallResponse::Ok(result) = response {
+            if result.decode::<bool>().map_err(|err| wasm_error!(err))? {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Whether the authorities of a message report it valid to this agent, asked
+/// by the message's author.
+#[hdk_extern]
+pub fn holds_message(action_hash: ActionHash) -> ExternResult<bool> {
+    Ok(matches!(
+        get_details(action_hash, GetOptions::latest())?,
+        Some(Details::Record(details)) if details.validation_status == ValidationStatus::Valid
+    ))
+}
@@ -0,0 +1,63 @@
+use hdk::prelude::*;
+use holomessage_integrity::{
+    DirectMessage, DirectMessageSent, EntryTypes, HoloMessageSignal, SendDirectMessageInput,
+    UnitEntryTypes,
+};
+
+#[hdk_extern]
+pub fn send_dm(input: SendDirectMessageInput) -> ExternResult<DirectMessageSent> {
+    let direct_message = DirectMessage {
+        from: agent_info()?.agent_latest_pubkey,
+        to: input.to.clone(),
+        content: input.content,
+    };
+    let action_hash = create_entry(EntryTypes::DirectMessage(direct_message.clone()))?;
+
+    // an offline or unreachable recipient results in a network error response
+    let response = call_remote(
+        input.to,
+        zome_info()?.name,
+        "recv_dm".into(),
+        None,
+        direct_message,
+    )?;
+    let delivered = matches!(response, ZomeCallResponse::Ok(_));
+    if !delivered {
+        debug!("direct message {action_hash} not delivered: {response:?}");
+    }
+
+    Ok(DirectMessageSent {
+        action_hash,
+        delivered,
+    })
+}
+
+#[hdk_extern]
+pub fn recv_dm(direct_message: DirectMessage) -> ExternResult<ActionHash> {
+    if direct_message.from != call_info()?.provenance {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "direct message sender does not match caller".to_string()
+        )));
+    }
+    if direct_message.to != agent_info()?.agent_latest_pubkey {
+        return Err(wasm_error!(WasmErrorInner::Guest(
+            "direct message is addressed to another agent".to_string()
+        )));
+    }
+    let action_hash = create_entry(EntryTypes::DirectMessage(direct_message.clone()))?;
+    emit_signal(HoloMessageSignal::DirectMessageReceived {
+        action_hash: action_hash.clone(),
+        timestamp: sys_time()?,
+        direct_message,
+    })?;
+    Ok(action_hash)
+}
+
+/// All direct messages sent and received by this agent, oldest first.
+#[hdk_extern]
+pub fn get_direct_messages(_: ()) -> ExternResult<Vec<Record>> {
+    let filter = ChainQueryFilter::new()
+        .entry_type(UnitEntryTypes::DirectMessage.try_into()?)
+        .include_entries(true);
+    query(filter)
+}
@@ -0,0 +1,71 @@
+use hdk::prelude::*;
+use holomessage_integrity::{HoloMessage, UnitEntryTypes};
+
+/// How often the author's node looks for expired messages.
+const EXPIRY_SCHEDULE: &str = "0 * * * * * *";
+
+/// Delete this agent's expired messages together with the links pointing to
+/// them. Runs every minute once scheduled in `init`.
+#[hdk_extern(infallible)]
+fn delete_expired_messages(_: Option<Schedule>) -> Option<Schedule> {
+    if let Err(err) = delete_my_expired_messages() {
+        error!("could not delete expired messages: {err:?}");
+    }
+    Some(Schedule::Persisted(EXPIRY_SCHEDULE.to_string()))
+}
+
+fn delete_my_expired_messages() -> ExternResult<()> {
+    let now = sys_time()?;
+    let deleted: HashSet<ActionHash> =
+        query(ChainQueryFilter::new().action_type(ActionType::Delete))?
+            .into_iter()
+            .filter_map(|record| match record.action() {
+                Action::Delete(delete) => Some(delete.deletes_address.clone()),
+                _ => None,
+            })
+            .collect();
+    let filter = ChainQueryFilter::new()
+        .entry_type(UnitEntryTypes::HoloMessage.try_into()?)
+        .include_entries(true);
+    let expired: Vec<ActionHash> = query(filter)?
+        .into_iter()
+        .filter(|record| !deleted.contains(record.action_address()))
+        .filter(|record| {
+            HoloMessage::try_from(record)
+                .ok()
+                .and_then(|holo_message| holo_message.expires_at)
+                .map_or(false, |expires_at| expires_at <= now)
+        })
+        .map(|record| record.action_address().clone())
+        .collect();
+    if expired.is_empty() {
+        return Ok(());
+    }
+
+    let deleted_links: HashSet<ActionHash> =
+        query(ChainQueryFilter::new().action_type(ActionType::DeleteLink))?
+            .into_iter()
+            .filter_map(|record| match record.action() {
+                Action::DeleteLink(delete_link) => Some(delete_link.link_add_address.clone()),
+                _ => None,
+            })
+            .collect();
+    // feed, author and mention links to the message were all created by the
+    // author, so they are found on the author's chain
+    for record in query(ChainQueryFilter::new().action_type(ActionType::CreateLink))? {
+        if let Action::CreateLink(create_link) = record.action() {
+            let is_expired = create_link
+                .target_address
+                .clone()
+                .into_action_hash()
+                .map_or(false, |target| expired.contains(&target));
+            if is_expired && !deleted_links.contains(record.action_address()) {
+                delete_link(record.action_address().clone())?;
+            }
+        }
+    }
+    for action_hash in expired {
+        delete_entry(action_hash)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use hdk::prelude::*;
+use holomessage_integrity::{Invite, InviteContent};
+
+/// How long an invite can be used to join.
+const INVITE_VALIDITY: std::time::Duration = std::time::Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Sign an invite to this network.
+#[hdk_extern]
+pub fn create_invite(_: ()) -> ExternResult<Invite> {
+    let agent_info = agent_info()?;
+    let expires_at = (sys_time()? + INVITE_VALIDITY)
+        .map_err(|err| wasm_error!(WasmErrorInner::Guest(err.to_string())))?;
+    let content = InviteContent {
+        inviter: agent_info.agent_latest_pubkey.clone(),
+        inviter_chain_top: agent_info.chain_head.0,
+        expires_at,
+    };
+    let signature = sign(agent_info.agent_latest_pubkey, content.clone())?;
+    Ok(Invite { content, signature })
+}
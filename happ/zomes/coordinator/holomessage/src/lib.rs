@@ -1,12 +1,94 @@
 use hdk::prelude::*;
-use holomessage_integrity::{EntryTypes, HoloMessage, LinkTypes};
+use holomessage_integrity::{
+    CreateMessageInput, EntryTypes, HoloMessage, LinkTypes, MessageContent, MessagesCursor,
+    MessagesPage, MessagesPageInput, VersionedHoloMessage,
+};
+
+mod attachment;
+mod delivery;
+mod direct_message;
+mod expiry;
+mod invite;
+mod mention;
+mod moderation;
+mod pin;
+mod poll;
+mod presence;
+mod profile;
+mod read_marker;
+mod signal;
 
 const ALL_MESSAGES_BASE: &str = "all_messages";
 
+#[hdk_extern]
+pub fn init(_: ()) -> ExternResult<InitCallbackResult> {
+    // allow other agents to deliver direct messages and signals to this cell,
+    // and to ask whether it holds their messages
+    let zome_name = zome_info()?.name;
+    let mut functions = BTreeSet::new();
+    functions.insert((zome_name.clone(), "recv_dm".into()));
+    functions.insert((zome_name.clone(), "holds_message".into()));
+    functions.insert((zome_name, "recv_remote_signal".into()));
+    create_cap_grant(CapGrantEntry {
+        tag: "remote_calls".into(),
+        access: CapAccess::Unrestricted,
+        functions: GrantedFunctions::Listed(functions),
+    })?;
+    schedule("delete_expired_messages")?;
+    Ok(InitCallbackResult::Pass)
+}
+
 #[hdk_extern]
 pub fn get_messages(_: ()) -> ExternResult<Vec<Record>> {
     let path = Path::from(ALL_MESSAGES_BASE);
-    let links = get_links(path.path_entry_hash()?, LinkTypes::HoloMessage, None)?;
+    get_linked_messages(path.path_entry_hash()?, LinkTypes::HoloMessage)
+}
+
+/// A page of the feed. Pages are cut by link timestamps, so only the
+/// messages of the page are fetched. Links of the same time are ordered by
+/// their target, so none of them falls between two pages.
+#[hdk_extern]
+pub fn get_messages_page(input: MessagesPageInput) -> ExternResult<MessagesPage> {
+    let path = Path::from(ALL_MESSAGES_BASE);
+    let before = input
+        .before
+        .map(|cursor| (cursor.timestamp, AnyLinkableHash::from(cursor.action_hash)));
+    let mut links: Vec<Link> = get_links(path.path_entry_hash()?, LinkTypes::HoloMessage, None)?
+        .into_iter()
+        .filter(|link| {
+            before.as_ref().map_or(true, |(timestamp, target)| {
+                (link.timestamp, &link.target) < (*timestamp, target)
+            })
+        })
+        .collect();
+    links.sort_by(|a, b| (b.timestamp, &b.target).cmp(&(a.timestamp, &a.target)));
+    let older = if links.len() > input.limit as usize {
+        links.truncate(input.limit as usize);
+        links.last().and_then(|link| {
+            Some(MessagesCursor {
+                timestamp: link.timestamp,
+                action_hash: link.target.clone().into_action_hash()?,
+            })
+        })
+    } else {
+        None
+    };
+    Ok(MessagesPage {
+        records: get_link_targets(links)?,
+        older,
+    })
+}
+
+/// Messages linked from `base`, newest first.
+pub(crate) fn get_linked_messages(
+    base: impl Into<AnyLinkableHash>,
+    link_type: LinkTypes,
+) -> ExternResult<Vec<Record>> {
+    get_link_targets(get_links(base, link_type, None)?)
+}
+
+/// Records the links point to, newest first.
+fn get_link_targets(links: Vec<Link>) -> ExternResult<Vec<Record>> {
     let get_inputs = links
         .into_iter()
         .map(|link| {
@@ -26,9 +108,15 @@ pub fn get_messages(_: ()) -> ExternResult<Vec<Record>> {
 }
 
 #[hdk_extern]
-pub fn create_message(message: String) -> ExternResult<ActionHash> {
-    let holo_message = HoloMessage { text: message };
-    let action_hash = create_entry(EntryTypes::HoloMessage(holo_message))?;
+pub fn create_message(input: CreateMessageInput) -> ExternResult<ActionHash> {
+    let holo_message = HoloMessage {
+        content: input.content,
+        attachment: input.attachment,
+        expires_at: input.expires_at,
+    };
+    let action_hash = create_entry(EntryTypes::HoloMessage(VersionedHoloMessage::V2(
+        holo_message.clone(),
+    )))?;
     // link to agent key base
     let agent_key = agent_info()?.agent_latest_pubkey;
     let _agent_link_hash = create_link(agent_key, action_hash.clone(), LinkTypes::HoloMessage, ())?;
@@ -40,6 +128,10 @@ pub fn create_message(message: String) -> ExternResult<ActionHash> {
         LinkTypes::HoloMessage,
         (),
     )?;
+    // mentions can only be resolved in readable messages
+    if let MessageContent::Text(text) = &holo_message.content {
+        mention::create_mention_links(text, &action_hash)?;
+    }
 
     Ok(action_hash)
 }
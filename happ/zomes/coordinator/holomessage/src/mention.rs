@@ -0,0 +1,80 @@
+use hc_zome_profiles_integrity::Profile;
+use hdk::prelude::*;
+use holomessage_integrity::{mentioned_nicknames, LinkTypes, MentionTag};
+
+use crate::get_linked_messages;
+
+/// Link every agent mentioned as `@nickname` in `text` to the message.
+/// Nicknames that don't belong to an agent are ignored.
+pub fn create_mention_links(text: &str, message_hash: &ActionHash) -> ExternResult<()> {
+    let mut mentioned_agents: Vec<(AgentPubKey, ActionHash)> = Vec::new();
+    for nickname in mentioned_nicknames(text) {
+        match resolve_nickname(nickname) {
+            Ok(Some((agent, profile)))
+                if !mentioned_agents
+                    .iter()
+                    .any(|(mentioned, _)| mentioned == &agent) =>
+            {
+                mentioned_agents.push((agent, profile))
+            }
+            Ok(_) => (),
+            Err(err) => debug!("could not resolve nickname {nickname}: {err:?}"),
+        }
+    }
+    for (agent, profile) in mentioned_agents {
+        create_link(
+            agent,
+            message_hash.clone(),
+            LinkTypes::Mention,
+            MentionTag { profile }.to_link_tag()?,
+        )?;
+    }
+    Ok(())
+}
+
+/// Messages mentioning this agent, newest first.
+#[hdk_extern]
+pub fn get_my_mentions(_: ()) -> ExternResult<Vec<Record>> {
+    let agent_key = agent_info()?.agent_latest_pubkey;
+    get_linked_messages(agent_key, LinkTypes::Mention)
+}
+
+/// The agent whose profile has exactly this nickname, with the record of
+/// the profile.
+fn resolve_nickname(nickname: &str) -> ExternResult<Option<(AgentPubKey, ActionHash)>> {
+    let links: Vec<Link> = call_profiles("search_agents", nickname.to_string())?;
+    for agent in links
+        .into_iter()
+        .filter_map(|link| link.target.into_agent_pub_key())
+    {
+        let record: Option<Record> = call_profiles("get_agent_profile", agent.clone())?;
+        if let Some(record) = record {
+            let profile_hash = record.action_address().clone();
+            if Profile::try_from(record)?.nickname == nickname {
+                return Ok(Some((agent, profile_hash)));
+            }
+        }
+    }
+    Ok(None)
+}
+
+pub(crate) fn call_profiles<I, O>(fn_name: &str, payload: I) -> ExternResult<O>
+where
+    I: Serialize + std::fmt::Debug,
+    O: serde::de::DeserializeOwned + std::fmt::Debug,
+{
+    match call(
+        CallTargetCell::Local,
+        ZomeName::from("profiles"),
+        fn_name.into(),
+        None,
+        payload,
+    )? {
+        ZomeCallResponse::Ok(result) => result
+            .decode()
+            .map_err(|err| wasm_error!(WasmErrorInner::Guest(err.to_string()))),
+        response => Err(wasm_error!(WasmErrorInner::Guest(format!(
+            "call to profiles zome failed: {response:?}"
+        )))),
+    }
+}
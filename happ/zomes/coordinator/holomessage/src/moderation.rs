@@ -0,0 +1,153 @@
+use hdk::prelude::*;
+use holomessage_integrity::{
+    dna_properties, EntryTypes, LinkTypes, Moderation, ModeratorGrant, ModeratorProof, FEED_CHANNEL,
+};
+
+const MODERATORS_PATH: &str = "moderators";
+const BLOCKED_AGENTS_PATH: &str = "blocked_agents";
+
+#[hdk_extern]
+pub fn grant_moderator(agent: AgentPubKey) -> ExternResult<ActionHash> {
+    let grant_hash = create_entry(EntryTypes::ModeratorGrant(ModeratorGrant { agent }))?;
+    create_link(
+        Path::from(MODERATORS_PATH).path_entry_hash()?,
+        grant_hash.clone(),
+        LinkTypes::Moderator,
+        (),
+    )?;
+    Ok(grant_hash)
+}
+
+#[hdk_extern]
+pub fn hide_message(message_hash: ActionHash) -> ExternResult<()> {
+    let feed_hash = Path::from(FEED_CHANNEL).path_entry_hash()?;
+    create_moderator_link(feed_hash, message_hash, LinkTypes::Hidden)
+}
+
+#[hdk_extern]
+pub fn unhide_message(message_hash: ActionHash) -> ExternResult<()> {
+    let feed_hash = Path::from(FEED_CHANNEL).path_entry_hash()?;
+    delete_links_to(feed_hash, message_hash, LinkTypes::Hidden)
+}
+
+/// Mark an agent as blocked for clients to hide the agent's messages. The
+/// agent can still create messages.
+#[hdk_extern]
+pub fn block_agent(agent: AgentPubKey) -> ExternResult<()> {
+    let blocked_hash = Path::from(BLOCKED_AGENTS_PATH).path_entry_hash()?;
+    create_moderator_link(blocked_hash, agent, LinkTypes::Blocked)
+}
+
+#[hdk_extern]
+pub fn unblock_agent(agent: AgentPubKey) -> ExternResult<()> {
+    let blocked_hash = Path::from(BLOCKED_AGENTS_PATH).path_entry_hash()?;
+    delete_links_to(blocked_hash, agent, LinkTypes::Blocked)
+}
+
+#[hdk_extern]
+pub fn get_moderation(_: ()) -> ExternResult<Moderation> {
+    let mut moderators: Vec<AgentPubKey> = dna_properties()?
+        .progenitor
+        .into_iter()
+        .map(AgentPubKey::from)
+        .collect();
+    for (_, grant) in get_grants()? {
+        if !moderators.contains(&grant.agent) {
+            moderators.push(grant.agent);
+        }
+    }
+    let hidden_messages = get_links(
+        Path::from(FEED_CHANNEL).path_entry_hash()?,
+        LinkTypes::Hidden,
+        None,
+    )?
+    .into_iter()
+    .filter_map(|link| link.target.into_action_hash())
+    .collect();
+    let blocked_agents = get_links(
+        Path::from(BLOCKED_AGENTS_PATH).path_entry_hash()?,
+        LinkTypes::Blocked,
+        None,
+    )?
+    .into_iter()
+    .filter_map(|link| link.target.into_agent_pub_key())
+    .collect();
+    Ok(Moderation {
+        moderators,
+        hidden_messages,
+        blocked_agents,
+    })
+}
+
+/// Proof of this agent's moderator role, to be put in link tags.
+pub(crate) fn my_moderator_proof() -> ExternResult<ModeratorProof> {
+    let agent = agent_info()?.agent_latest_pubkey;
+    if dna_properties()?.is_progenitor(&agent) {
+        return Ok(ModeratorProof { grant: None });
+    }
+    match get_grants()?
+        .into_iter()
+        .find(|(_, grant)| grant.agent == agent)
+    {
+        Some((grant_hash, _)) => Ok(ModeratorProof {
+            grant: Some(grant_hash),
+        }),
+        None => Err(wasm_error!(WasmErrorInner::Guest(
+            "agent is not a moderator".to_string()
+        ))),
+    }
+}
+
+/// All moderator grants with their action hashes.
+fn get_grants() -> ExternResult<Vec<(ActionHash, ModeratorGrant)>> {
+    let links = get_links(
+        Path::from(MODERATORS_PATH).path_entry_hash()?,
+        LinkTypes::Moderator,
+        None,
+    )?;
+    let mut grants = Vec::new();
+    for grant_hash in links
+        .into_iter()
+        .filter_map(|link| link.target.into_action_hash())
+    {
+        if let Some(record) = get(grant_hash.clone(), GetOptions::default())? {
+            grants.push((grant_hash, ModeratorGrant::try_from(record)?));
+        }
+    }
+    Ok(grants)
+}
+
+fn create_moderator_link(
+    base: impl Into<AnyLinkableHash>,
+    target: impl Into<AnyLinkableHash>,
+    link_type: LinkTypes,
+) -> ExternResult<()> {
+    let base = base.into();
+    let target = target.into();
+    let already_linked = get_links(base.clone(), link_type, None)?
+        .into_iter()
+        .any(|link| link.target == target);
+    if !already_linked {
+        create_link(
+            base,
+            target,
+            link_type,
+            my_moderator_proof()?.to_link_tag()?,
+        )?;
+    }
+    Ok(())
+}
+
+pub(crate) fn delete_links_to(
+    base: impl Into<AnyLinkableHash>,
+    target: impl Into<AnyLinkableHash>,
+    link_type: LinkTypes,
+) -> ExternResult<()> {
+    let target = target.into();
+    for link in get_links(base, link_type, None)? {
+        if link.target == target {
+            delete_link(link.create_link_hash)?;
+        }
+    }
+    Ok(())
+}
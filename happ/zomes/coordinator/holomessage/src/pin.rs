@@ -0,0 +1,43 @@
+use hdk::prelude::*;
+use holomessage_integrity::{
+    dna_properties, HoloMessageProperties, LinkTypes, PinPermission, FEED_CHANNEL,
+};
+
+use crate::{
+    get_linked_messages,
+    moderation::{delete_links_to, my_moderator_proof},
+};
+
+#[hdk_extern]
+pub fn pin_message(message_hash: ActionHash) -> ExternResult<()> {
+    let feed_hash = Path::from(FEED_CHANNEL).path_entry_hash()?;
+    let already_pinned = get_links(feed_hash.clone(), LinkTypes::Pin, None)?
+        .into_iter()
+        .any(|link| link.target == message_hash.clone().into());
+    if !already_pinned {
+        let tag = match dna_properties()?.pin_permission {
+            PinPermission::Anyone => LinkTag::new(Vec::new()),
+            PinPermission::Moderators => my_moderator_proof()?.to_link_tag()?,
+        };
+        create_link(feed_hash, message_hash, LinkTypes::Pin, tag)?;
+    }
+    Ok(())
+}
+
+#[hdk_extern]
+pub fn unpin_message(message_hash: ActionHash) -> ExternResult<()> {
+    let feed_hash = Path::from(FEED_CHANNEL).path_entry_hash()?;
+    delete_links_to(feed_hash, message_hash, LinkTypes::Pin)
+}
+
+/// Pinned messages of the feed, newest first.
+#[hdk_extern]
+pub fn get_pinned(_: ()) -> ExternResult<Vec<Record>> {
+    let feed_hash = Path::from(FEED_CHANNEL).path_entry_hash()?;
+    get_linked_messages(feed_hash, LinkTypes::Pin)
+}
+
+#[hdk_extern]
+pub fn get_dna_properties(_: ()) -> ExternResult<HoloMessageProperties> {
+    dna_properties()
+}
@@ -0,0 +1,85 @@
+use hdk::prelude::*;
+use holomessage_integrity::{EntryTypes, LinkTypes, Poll, PollResults, Vote, FEED_CHANNEL};
+
+#[hdk_extern]
+pub fn create_poll(poll: Poll) -> ExternResult<ActionHash> {
+    let poll_hash = create_entry(EntryTypes::Poll(poll))?;
+    let feed_hash = Path::from(FEED_CHANNEL).path_entry_hash()?;
+    create_link(feed_hash, poll_hash.clone(), LinkTypes::Poll, ())?;
+    Ok(poll_hash)
+}
+
+#[hdk_extern]
+pub fn vote(vote: Vote) -> ExternResult<ActionHash> {
+    let poll_hash = vote.poll.clone();
+    let vote_hash = create_entry(EntryTypes::Vote(vote))?;
+    create_link(poll_hash, vote_hash.clone(), LinkTypes::Vote, ())?;
+    Ok(vote_hash)
+}
+
+/// Polls of the feed with their results, newest first.
+#[hdk_extern]
+pub fn get_polls(_: ()) -> ExternResult<Vec<PollResults>> {
+    let feed_hash = Path::from(FEED_CHANNEL).path_entry_hash()?;
+    let mut polls = Vec::new();
+    for link in get_links(feed_hash, LinkTypes::Poll, None)? {
+        if let Some(poll_hash) = link.target.into_action_hash() {
+            if let Some(results) = poll_results(poll_hash)? {
+                polls.push(results);
+            }
+        }
+    }
+    polls.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(polls)
+}
+
+#[hdk_extern]
+pub fn get_poll_results(poll_hash: ActionHash) -> ExternResult<PollResults> {
+    poll_results(poll_hash.clone())?.ok_or(wasm_error!(WasmErrorInner::Guest(format!(
+        "poll {poll_hash} not found"
+    ))))
+}
+
+fn poll_results(poll_hash: ActionHash) -> ExternResult<Option<PollResults>> {
+    let Some(record) = get(poll_hash.clone(), GetOptions::default())? else {
+        return Ok(None);
+    };
+    let poll = Poll::try_from(&record)?;
+    let get_inputs = get_links(poll_hash.clone(), LinkTypes::Vote, None)?
+        .into_iter()
+        .filter_map(|link| link.target.into_action_hash())
+        .map(|vote_hash| GetInput::new(vote_hash.into(), GetOptions::default()))
+        .collect();
+    let my_agent_key = agent_info()?.agent_latest_pubkey;
+    let mut votes = vec![0; poll.options.len()];
+    let mut my_vote = None;
+    // validation allows one vote per agent, duplicates are only counted once
+    let mut voters = HashSet::new();
+    for vote_record in HDK
+        .with(|hdk| hdk.borrow().get(get_inputs))?
+        .into_iter()
+        .flatten()
+    {
+        let voter = vote_record.action().author().clone();
+        let Ok(vote) = Vote::try_from(&vote_record) else {
+            continue;
+        };
+        if vote.poll != poll_hash || !voters.insert(voter.clone()) {
+            continue;
+        }
+        if let Some(count) = votes.get_mut(vote.option as usize) {
+            *count += 1;
+        }
+        if voter == my_agent_key {
+            my_vote = Some(vote.option);
+        }
+    }
+    Ok(Some(PollResults {
+        poll_hash,
+        author: record.action().author().clone(),
+        created_at: record.action().timestamp(),
+        poll,
+        votes,
+        my_vote,
+    }))
+}
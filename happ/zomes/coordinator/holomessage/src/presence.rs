@@ -0,0 +1,61 @@
+use hdk::prelude::*;
+use holomessage_integrity::{
+    dna_properties, LinkTypes, Presence, PresenceStatus, PresenceTag, PRESENCE_PATH,
+};
+
+/// Heartbeat of this agent. Replaces the agent's previous presence links.
+#[hdk_extern]
+pub fn announce_presence(status: PresenceStatus) -> ExternResult<()> {
+    let presence_hash = Path::from(PRESENCE_PATH).path_entry_hash()?;
+    let my_agent_key = agent_info()?.agent_latest_pubkey;
+    let previous_links: Vec<Link> = get_links(presence_hash.clone(), LinkTypes::Presence, None)?
+        .into_iter()
+        .filter(|link| link.author == my_agent_key)
+        .collect();
+    let tag = PresenceTag {
+        status,
+        timestamp: sys_time()?,
+    };
+    create_link(
+        presence_hash,
+        my_agent_key,
+        LinkTypes::Presence,
+        tag.to_link_tag()?,
+    )?;
+    for link in previous_links {
+        delete_link(link.create_link_hash)?;
+    }
+    Ok(())
+}
+
+/// Agents with a heartbeat within the presence timeout of the DNA properties.
+#[hdk_extern]
+pub fn get_online_agents(_: ()) -> ExternResult<Vec<Presence>> {
+    let presence_hash = Path::from(PRESENCE_PATH).path_entry_hash()?;
+    let timeout_micros = dna_properties()?.presence_timeout_seconds as i64 * 1_000_000;
+    let now = sys_time()?;
+    let mut presences: BTreeMap<AgentPubKey, Presence> = BTreeMap::new();
+    for link in get_links(presence_hash, LinkTypes::Presence, None)? {
+        let Ok(tag) = PresenceTag::from_link_tag(&link.tag) else {
+            continue;
+        };
+        if now.as_micros() - tag.timestamp.as_micros() > timeout_micros {
+            continue;
+        }
+        // an agent's previous links may not be deleted everywhere yet
+        let is_newer = presences
+            .get(&link.author)
+            .map_or(true, |presence| presence.last_seen < tag.timestamp);
+        if is_newer {
+            presences.insert(
+                link.author.clone(),
+                Presence {
+                    agent: link.author,
+                    status: tag.status,
+                    last_seen: tag.timestamp,
+                },
+            );
+        }
+    }
+    Ok(presences.into_values().collect())
+}
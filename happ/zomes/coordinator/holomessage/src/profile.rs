@@ -0,0 +1,18 @@
+use hdk::prelude::*;
+
+use crate::mention::call_profiles;
+
+/// Profile records of several agents in one call, `None` for agents without
+/// a profile.
+#[hdk_extern]
+pub fn get_agent_profiles(
+    agents: Vec<AgentPubKey>,
+) -> ExternResult<Vec<(AgentPubKey, Option<Record>)>> {
+    agents
+        .into_iter()
+        .map(|agent| {
+            let record: Option<Record> = call_profiles("get_agent_profile", agent.clone())?;
+            Ok((agent, record))
+        })
+        .collect()
+}
@@ -0,0 +1,27 @@
+use hdk::prelude::*;
+use holomessage_integrity::{EntryTypes, ReadMarker, UnitEntryTypes};
+
+#[hdk_extern]
+pub fn set_read_marker(read_marker: ReadMarker) -> ExternResult<ActionHash> {
+    create_entry(EntryTypes::ReadMarker(read_marker))
+}
+
+/// The latest read marker of every channel.
+#[hdk_extern]
+pub fn get_read_markers(_: ()) -> ExternResult<Vec<ReadMarker>> {
+    let filter = ChainQueryFilter::new()
+        .entry_type(UnitEntryTypes::ReadMarker.try_into()?)
+        .include_entries(true);
+    let mut read_markers: BTreeMap<String, ReadMarker> = BTreeMap::new();
+    for read_marker in query(filter)?.into_iter().flat_map(ReadMarker::try_from) {
+        let is_newer = read_markers
+            .get(&read_marker.channel)
+            .map_or(true, |current| {
+                current.last_seen_at <= read_marker.last_seen_at
+            });
+        if is_newer {
+            read_markers.insert(read_marker.channel.clone(), read_marker);
+        }
+    }
+    Ok(read_markers.into_values().collect())
+}
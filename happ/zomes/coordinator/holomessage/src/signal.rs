@@ -0,0 +1,33 @@
+use hdk::prelude::*;
+use holomessage_integrity::{HoloMessageSignal, NotifyTypingInput, RemoteSignal};
+
+#[hdk_extern]
+pub fn notify_typing(input: NotifyTypingInput) -> ExternResult<()> {
+    send_remote_signal(
+        RemoteSignal::Typing {
+            nickname: input.nickname,
+        },
+        input.agents,
+    )
+}
+
+/// Tell other agents to reload this agent's profile.
+#[hdk_extern]
+pub fn notify_profile_updated(agents: Vec<AgentPubKey>) -> ExternResult<()> {
+    send_remote_signal(RemoteSignal::ProfileUpdated, agents)
+}
+
+/// Forward signals from other agents to the local client.
+#[hdk_extern]
+pub fn recv_remote_signal(signal: ExternIO) -> ExternResult<()> {
+    let remote_signal: RemoteSignal = signal
+        .decode()
+        .map_err(|err| wasm_error!(WasmErrorInner::Guest(err.to_string())))?;
+    let agent = call_info()?.provenance;
+    match remote_signal {
+        RemoteSignal::Typing { nickname } => {
+            emit_signal(HoloMessageSignal::Typing { agent, nickname })
+        }
+        RemoteSignal::ProfileUpdated => emit_signal(HoloMessageSignal::ProfileUpdated { agent }),
+    }
+}
@@ -1,28 +1,154 @@
-use hdk::prelude::Record;
+use hdk::prelude::{AgentPubKey, Record, SerializedBytes};
+use holochain::prelude::{DnaFile, DnaModifiersOpt};
 use holochain::sweettest::SweetConductor;
-use holochain::sweettest::{SweetConductorConfig, SweetLocalRendezvous};
+use holochain::sweettest::{
+    SweetAgents, SweetApp, SweetCell, SweetConductorConfig, SweetDnaFile, SweetLocalRendezvous,
+};
+use holomessage_integrity::HoloMessageProperties;
+use std::path::Path;
 
-#[cfg(test)]
-#[tokio::test(flavor = "multi_thread")]
-async fn messages() {
-    use hdk::prelude::ActionHash;
-    use holochain::sweettest::{SweetAgents, SweetDnaFile};
-    use holomessage_integrity::HoloMessage;
-    use std::path::Path;
-
-    let mut conductor = SweetConductor::from_config_rendezvous(
+async fn new_conductor() -> SweetConductor {
+    SweetConductor::from_config_rendezvous(
         SweetConductorConfig::rendezvous(true),
         SweetLocalRendezvous::new().await,
     )
-    .await;
+    .await
+}
+
+/// The hApp's DNA, with `properties` instead of those of the bundle if given.
+async fn holomessage_dna(properties: Option<HoloMessageProperties>) -> DnaFile {
     let dna_file_path = Path::new("../../../workdir/holomessage.dna");
     let dna_file = SweetDnaFile::from_bundle(dna_file_path).await.unwrap();
-    let zome_name = "holomessage";
-    let agents = SweetAgents::get(conductor.keystore(), 2).await;
+    match properties {
+        Some(properties) => dna_file.update_modifiers(
+            DnaModifiersOpt::none().with_properties(SerializedBytes::try_from(properties).unwrap()),
+        ),
+        None => dna_file,
+    }
+}
+
+/// A conductor with the hApp installed for `agents` new agents.
+async fn setup(agents: usize) -> (SweetConductor, Vec<AgentPubKey>, Vec<SweetApp>) {
+    setup_with_properties(agents, None).await
+}
+
+/// Like `setup`, with `properties` instead of the DNA properties of the
+/// bundle if given.
+async fn setup_with_properties(
+    agents: usize,
+    properties: Option<HoloMessageProperties>,
+) -> (SweetConductor, Vec<AgentPubKey>, Vec<SweetApp>) {
+    let mut conductor = new_conductor().await;
+    let dna_file = holomessage_dna(properties).await;
+    let agents = SweetAgents::get(conductor.keystore(), agents).await;
     let apps = conductor
         .setup_app_for_agents("", &agents, [&dna_file])
         .await
+        .unwrap()
+        .into_inner();
+    (conductor, agents, apps)
+}
+
+/// Commit a feed message to the chain of `cell` without validating it first,
+/// as a modified conductor could, and publish it to peers.
+async fn commit_unvalidated_message(
+    conductor: &SweetConductor,
+    cell: &SweetCell,
+    holo_message: holomessage_integrity::HoloMessage,
+) -> hdk::prelude::ActionHash {
+    use hdk::prelude::{Entry, EntryDefLocation, EntryVisibility};
+    use holochain::test_utils::host_fn_caller::HostFnCaller;
+    use holomessage_integrity::VersionedHoloMessage;
+
+    let dna_file = conductor.get_dna_file(cell.cell_id().dna_hash()).unwrap();
+    // feed messages are the first entry type of the hApp's integrity zome,
+    // which comes after the one of the profiles zome
+    let zome_index = dna_file
+        .dna_def()
+        .integrity_zomes
+        .iter()
+        .position(|(zome_name, _)| zome_name.0 == "holomessage_integrity")
+        .unwrap() as u8;
+    let caller = HostFnCaller::create(cell.cell_id(), &conductor.raw_handle(), &dna_file).await;
+    let entry = Entry::try_from(VersionedHoloMessage::V2(holo_message)).unwrap();
+    caller
+        .commit_entry(
+            entry,
+            EntryDefLocation::app(zome_index, 0),
+            EntryVisibility::Public,
+        )
+        .await
+}
+
+/// Install and enable the hApp with `dna_file` for `agent`, with `invite` as
+/// membrane proof like the app when started with an invite code.
+async fn install_with_invite(
+    conductor: &SweetConductor,
+    agent: AgentPubKey,
+    dna_file: &DnaFile,
+    invite: holomessage_integrity::Invite,
+) -> Result<SweetCell, String> {
+    use holochain::prelude::{
+        AppBundle, AppBundleSource, AppManifestCurrentBuilder, AppRoleDnaManifest, AppRoleManifest,
+        CellId, CellProvisioning, InstallAppPayload,
+    };
+    use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+    let role_name = "holomessage".to_string();
+    let installed_app_id = agent.to_string();
+    conductor
+        .raw_handle()
+        .register_dna(dna_file.clone())
+        .await
+        .map_err(|err| format!("{err:?}"))?;
+    let manifest = AppManifestCurrentBuilder::default()
+        .name(installed_app_id.clone())
+        .description(None)
+        .roles(vec![AppRoleManifest {
+            name: role_name.clone(),
+            provisioning: Some(CellProvisioning::Create { deferred: false }),
+            dna: AppRoleDnaManifest {
+                location: None,
+                modifiers: DnaModifiersOpt::none(),
+                installed_hash: Some(dna_file.dna_hash().clone().into()),
+                clone_limit: 0,
+            },
+        }])
+        .build()
         .unwrap();
+    let bundle = AppBundle::new(manifest.into(), vec![], PathBuf::from("."))
+        .await
+        .unwrap();
+    let membrane_proof = Arc::new(SerializedBytes::try_from(invite).unwrap());
+    conductor
+        .raw_handle()
+        .install_app_bundle(InstallAppPayload {
+            source: AppBundleSource::Bundle(bundle),
+            agent_key: agent.clone(),
+            installed_app_id: Some(installed_app_id.clone()),
+            membrane_proofs: HashMap::from([(role_name, membrane_proof)]),
+            network_seed: None,
+        })
+        .await
+        .map_err(|err| format!("{err:?}"))?;
+    conductor
+        .raw_handle()
+        .enable_app(installed_app_id)
+        .await
+        .map_err(|err| format!("{err:?}"))?;
+    Ok(conductor
+        .get_sweet_cell(CellId::new(dna_file.dna_hash().clone(), agent))
+        .unwrap())
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn messages() {
+    use hdk::prelude::ActionHash;
+    use holomessage_integrity::{CreateMessageInput, HoloMessage, MessageContent};
+
+    let (mut conductor, _, apps) = setup(2).await;
+    let zome_name = "holomessage";
 
     // check messages for one agent
     let zome = apps[0].cells()[0].zome(zome_name);
@@ -31,7 +157,17 @@ async fn messages() {
     assert_eq!(messages.len(), 0);
 
     let message_1 = "text_1";
-    let _action_hash: ActionHash = conductor.call(&zome, "create_message", message_1).await;
+    let _action_hash: ActionHash = conductor
+        .call(
+            &zome,
+            "create_message",
+            CreateMessageInput {
+                content: MessageContent::Text(message_1.to_string()),
+                attachment: None,
+                expires_at: None,
+            },
+        )
+        .await;
 
     println!("does this fail 2?");
     let messages: Vec<Record> = conductor.call(&zome, "get_messages", ()).await;
@@ -43,7 +179,9 @@ async fn messages() {
     assert_eq!(
         messages,
         vec![HoloMessage {
-            text: message_1.to_string()
+            content: MessageContent::Text(message_1.to_string()),
+            attachment: None,
+            expires_at: None,
         }]
     );
 
@@ -60,14 +198,26 @@ async fn messages() {
     assert_eq!(
         messages,
         vec![HoloMessage {
-            text: message_1.to_string()
+            content: MessageContent::Text(message_1.to_string()),
+            attachment: None,
+            expires_at: None,
         }]
     );
 
     // 2nd agent creates message and should see two messages
     // ordered by timestamp
     let message_2 = "text_2";
-    let _action_hash: ActionHash = conductor.call(&zome, "create_message", message_2).await;
+    let _action_hash: ActionHash = conductor
+        .call(
+            &zome,
+            "create_message",
+            CreateMessageInput {
+                content: MessageContent::Text(message_2.to_string()),
+                attachment: None,
+                expires_at: None,
+            },
+        )
+        .await;
 
     let messages: Vec<Record> = conductor.call(&zome, "get_messages", ()).await;
     let messages: Vec<HoloMessage> = messages
@@ -79,11 +229,735 @@ async fn messages() {
         messages,
         vec![
             HoloMessage {
-                text: message_2.to_string()
+                content: MessageContent::Text(message_2.to_string()),
+                attachment: None,
+                expires_at: None,
             },
             HoloMessage {
-                text: message_1.to_string()
+                content: MessageContent::Text(message_1.to_string()),
+                attachment: None,
+                expires_at: None,
             }
         ]
     );
 }
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn message_delivery() {
+    use hdk::prelude::ActionHash;
+    use holochain::sweettest::consistency_10s;
+    use holomessage_integrity::{CreateMessageInput, MessageContent, PresenceStatus};
+
+    let (mut conductor, _, apps) = setup(2).await;
+    let cells = [&apps[0].cells()[0], &apps[1].cells()[0]];
+    let alice = cells[0].zome("holomessage");
+    let bob = cells[1].zome("holomessage");
+
+    let action_hash: ActionHash = conductor
+        .call(
+            &alice,
+            "create_message",
+            CreateMessageInput {
+                content: MessageContent::Text("hi".to_string()),
+                attachment: None,
+                expires_at: None,
+            },
+        )
+        .await;
+    consistency_10s(cells).await;
+
+    // nobody else is online to report getting the message
+    let seen: bool = conductor
+        .call(&alice, "is_message_seen", action_hash.clone())
+        .await;
+    assert!(!seen);
+
+    let _: () = conductor
+        .call(&bob, "announce_presence", PresenceStatus::Online)
+        .await;
+    consistency_10s(cells).await;
+    let seen: bool = conductor.call(&alice, "is_message_seen", action_hash).await;
+    assert!(seen);
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn direct_messages() {
+    use holochain::sweettest::consistency_10s;
+    use holomessage_integrity::{
+        DirectMessage, DirectMessageSent, MessageContent, SendDirectMessageInput,
+    };
+
+    let (mut conductor, agents, apps) = setup(2).await;
+    let zome_name = "holomessage";
+    let alice = apps[0].cells()[0].zome(zome_name);
+    let bob = apps[1].cells()[0].zome(zome_name);
+    consistency_10s([&apps[0].cells()[0], &apps[1].cells()[0]]).await;
+
+    let sent: DirectMessageSent = conductor
+        .call(
+            &alice,
+            "send_dm",
+            SendDirectMessageInput {
+                to: agents[1].clone(),
+                content: MessageContent::Text("hi bob".to_string()),
+            },
+        )
+        .await;
+    assert!(sent.delivered);
+
+    // both source chains hold the direct message
+    for zome in [&alice, &bob] {
+        let records: Vec<Record> = conductor.call(zome, "get_direct_messages", ()).await;
+        let direct_messages: Vec<DirectMessage> = records
+            .into_iter()
+            .map(TryFrom::try_from)
+            .flatten()
+            .collect();
+        assert_eq!(
+            direct_messages,
+            vec![DirectMessage {
+                from: agents[0].clone(),
+                to: agents[1].clone(),
+                content: MessageContent::Text("hi bob".to_string())
+            }]
+        );
+    }
+
+    // direct messages are not published to the shared feed
+    let messages: Vec<Record> = conductor.call(&bob, "get_messages", ()).await;
+    assert_eq!(messages.len(), 0);
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn mentions() {
+    use hc_zome_profiles_integrity::Profile;
+    use hdk::prelude::ActionHash;
+    use holochain::sweettest::consistency_10s;
+    use holomessage_integrity::{CreateMessageInput, MessageContent};
+    use std::collections::BTreeMap;
+
+    let (mut conductor, _, apps) = setup(2).await;
+    let alice_profiles = apps[0].cells()[0].zome("profiles");
+    let alice = apps[0].cells()[0].zome("holomessage");
+    let bob = apps[1].cells()[0].zome("holomessage");
+
+    let _profile: Record = conductor
+        .call(
+            &alice_profiles,
+            "create_profile",
+            Profile {
+                nickname: "alice".to_string(),
+                fields: BTreeMap::new(),
+            },
+        )
+        .await;
+    consistency_10s([&apps[0].cells()[0], &apps[1].cells()[0]]).await;
+
+    let action_hash: ActionHash = conductor
+        .call(
+            &bob,
+            "create_message",
+            CreateMessageInput {
+                content: MessageContent::Text("hello @alice, and @nobody".to_string()),
+                attachment: None,
+                expires_at: None,
+            },
+        )
+        .await;
+    consistency_10s([&apps[0].cells()[0], &apps[1].cells()[0]]).await;
+
+    let mentions: Vec<Record> = conductor.call(&alice, "get_my_mentions", ()).await;
+    assert_eq!(
+        mentions
+            .iter()
+            .map(|record| record.action_address().clone())
+            .collect::<Vec<_>>(),
+        vec![action_hash]
+    );
+    let mentions: Vec<Record> = conductor.call(&bob, "get_my_mentions", ()).await;
+    assert_eq!(mentions.len(), 0);
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn pinned_messages() {
+    use hdk::prelude::ActionHash;
+    use holochain::sweettest::consistency_10s;
+    use holomessage_integrity::{CreateMessageInput, MessageContent};
+
+    let (mut conductor, _, apps) = setup(2).await;
+    let alice = apps[0].cells()[0].zome("holomessage");
+    let bob = apps[1].cells()[0].zome("holomessage");
+
+    let action_hash: ActionHash = conductor
+        .call(
+            &alice,
+            "create_message",
+            CreateMessageInput {
+                content: MessageContent::Text("pin me".to_string()),
+                attachment: None,
+                expires_at: None,
+            },
+        )
+        .await;
+    consistency_10s([&apps[0].cells()[0], &apps[1].cells()[0]]).await;
+
+    // anyone may pin with the default DNA properties
+    let _: () = conductor
+        .call(&bob, "pin_message", action_hash.clone())
+        .await;
+    consistency_10s([&apps[0].cells()[0], &apps[1].cells()[0]]).await;
+    let pinned: Vec<Record> = conductor.call(&alice, "get_pinned", ()).await;
+    assert_eq!(
+        pinned
+            .iter()
+            .map(|record| record.action_address().clone())
+            .collect::<Vec<_>>(),
+        vec![action_hash.clone()]
+    );
+
+    let _: () = conductor.call(&alice, "unpin_message", action_hash).await;
+    consistency_10s([&apps[0].cells()[0], &apps[1].cells()[0]]).await;
+    let pinned: Vec<Record> = conductor.call(&bob, "get_pinned", ()).await;
+    assert_eq!(pinned.len(), 0);
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn moderation() {
+    use hdk::prelude::ActionHash;
+    use holochain::sweettest::consistency_10s;
+    use holomessage_integrity::{CreateMessageInput, MessageContent, Moderation, PinPermission};
+
+    let mut conductor = new_conductor().await;
+    let agents = SweetAgents::get(conductor.keystore(), 3).await;
+    // alice is the progenitor, only moderators may pin
+    let dna_file = holomessage_dna(Some(HoloMessageProperties {
+        progenitor: Some(agents[0].clone().into()),
+        pin_permission: PinPermission::Moderators,
+        ..Default::default()
+    }))
+    .await;
+    let apps = conductor
+        .setup_app_for_agents("", &agents, [&dna_file])
+        .await
+        .unwrap();
+    let cells = [
+        &apps[0].cells()[0],
+        &apps[1].cells()[0],
+        &apps[2].cells()[0],
+    ];
+    let alice = cells[0].zome("holomessage");
+    let bob = cells[1].zome("holomessage");
+    let carol = cells[2].zome("holomessage");
+
+    let action_hash: ActionHash = conductor
+        .call(
+            &carol,
+            "create_message",
+            CreateMessageInput {
+                content: MessageContent::Text("spam".to_string()),
+                attachment: None,
+                expires_at: None,
+            },
+        )
+        .await;
+    consistency_10s(cells).await;
+
+    // bob is no moderator yet
+    let result: Result<(), _> = conductor
+        .call_fallible(&bob, "hide_message", action_hash.clone())
+        .await;
+    assert!(result.is_err());
+    let result: Result<ActionHash, _> = conductor
+        .call_fallible(&bob, "grant_moderator", agents[1].clone())
+        .await;
+    assert!(result.is_err());
+
+    let _grant_hash: ActionHash = conductor
+        .call(&alice, "grant_moderator", agents[1].clone())
+        .await;
+    consistency_10s(cells).await;
+
+    let _: () = conductor
+        .call(&bob, "hide_message", action_hash.clone())
+        .await;
+    let _: () = conductor.call(&bob, "block_agent", agents[2].clone()).await;
+    let _: () = conductor
+        .call(&bob, "pin_message", action_hash.clone())
+        .await;
+    let result: Result<(), _> = conductor
+        .call_fallible(&carol, "unpin_message", action_hash.clone())
+        .await;
+    assert!(result.is_err());
+    consistency_10s(cells).await;
+
+    let moderation: Moderation = conductor.call(&carol, "get_moderation", ()).await;
+    assert_eq!(
+        moderation,
+        Moderation {
+            moderators: vec![agents[0].clone(), agents[1].clone()],
+            hidden_messages: vec![action_hash],
+            blocked_agents: vec![agents[2].clone()],
+        }
+    );
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn moderator_grants() {
+    use hdk::prelude::ActionHash;
+    use holochain::sweettest::consistency_10s;
+    use holomessage_integrity::{CreateMessageInput, MessageContent};
+
+    let mut conductor = new_conductor().await;
+    let agents = SweetAgents::get(conductor.keystore(), 2).await;
+    let dna_file = holomessage_dna(Some(HoloMessageProperties {
+        progenitor: Some(agents[0].clone().into()),
+        ..Default::default()
+    }))
+    .await;
+    let apps = conductor
+        .setup_app_for_agents("", &agents, [&dna_file])
+        .await
+        .unwrap();
+    let cells = [&apps[0].cells()[0], &apps[1].cells()[0]];
+    let alice = cells[0].zome("holomessage");
+    let bob = cells[1].zome("holomessage");
+
+    let action_hash: ActionHash = conductor
+        .call(
+            &alice,
+            "create_message",
+            CreateMessageInput {
+                content: MessageContent::Text("hi".to_string()),
+                attachment: None,
+                expires_at: None,
+            },
+        )
+        .await;
+    consistency_10s(cells).await;
+
+    // a grant bob commits for their own key is rejected as a record, not
+    // only as the link to it
+    let result: Result<ActionHash, _> = conductor
+        .call_fallible(&bob, "grant_moderator", agents[1].clone())
+        .await;
+    let err = format!("{:?}", result.unwrap_err());
+    assert!(err.contains("only the progenitor may grant moderator status"));
+
+    let result: Result<(), _> = conductor
+        .call_fallible(&bob, "hide_message", action_hash)
+        .await;
+    assert!(result.is_err());
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn invite_only() {
+    use hdk::prelude::{ActionHash, Signature};
+    use holochain::sweettest::consistency_10s;
+    use holomessage_integrity::{CreateMessageInput, Invite, MessageContent};
+
+    let mut conductor = new_conductor().await;
+    let agents = SweetAgents::get(conductor.keystore(), 4).await;
+    let dna_file = holomessage_dna(Some(HoloMessageProperties {
+        progenitor: Some(agents[0].clone().into()),
+        invite_only: true,
+        ..Default::default()
+    }))
+    .await;
+
+    // the progenitor joins without an invite and can invite others
+    let alice_app = conductor
+        .setup_app_for_agent("alice", agents[0].clone(), [&dna_file])
+        .await
+        .unwrap();
+    let alice = alice_app.cells()[0].zome("holomessage");
+    let invite: Invite = conductor.call(&alice, "create_invite", ()).await;
+    assert_eq!(invite.content.inviter, agents[0]);
+
+    // genesis self check rejects agents without an invite
+    let result = conductor
+        .setup_app_for_agent("bob", agents[1].clone(), [&dna_file])
+        .await;
+    assert!(result.is_err());
+
+    // and agents with an invite the inviter didn't sign
+    let forged_invite = Invite {
+        signature: Signature([0; 64]),
+        ..invite.clone()
+    };
+    let result = install_with_invite(&conductor, agents[2].clone(), &dna_file, forged_invite).await;
+    assert!(result.is_err());
+
+    // the invitee joins and can commit to the network
+    let dave_cell = install_with_invite(&conductor, agents[3].clone(), &dna_file, invite)
+        .await
+        .unwrap();
+    let dave = dave_cell.zome("holomessage");
+    let _: ActionHash = conductor
+        .call(
+            &dave,
+            "create_message",
+            CreateMessageInput {
+                content: MessageContent::Text("thanks for the invite".to_string()),
+                attachment: None,
+                expires_at: None,
+            },
+        )
+        .await;
+    consistency_10s([&alice_app.cells()[0], &dave_cell]).await;
+    let messages: Vec<Record> = conductor.call(&alice, "get_messages", ()).await;
+    assert_eq!(messages.len(), 1);
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn message_rate_limit() {
+    use hdk::prelude::ActionHash;
+    use holochain::sweettest::consistency_10s;
+    use holomessage_integrity::{CreateMessageInput, HoloMessage, MessageContent, RateLimit};
+
+    let properties = HoloMessageProperties {
+        message_rate_limit: Some(RateLimit {
+            max_messages: 2,
+            period_seconds: 60,
+        }),
+        ..Default::default()
+    };
+    let (mut conductor, _, apps) = setup_with_properties(2, Some(properties)).await;
+    let cells = [&apps[0].cells()[0], &apps[1].cells()[0]];
+    let alice = cells[0].zome("holomessage");
+    let bob = cells[1].zome("holomessage");
+    let message = |text: &str| CreateMessageInput {
+        content: MessageContent::Text(text.to_string()),
+        attachment: None,
+        expires_at: None,
+    };
+
+    // bob floods the feed, the message over the limit is rejected
+    let _: ActionHash = conductor
+        .call(&bob, "create_message", message("flood 1"))
+        .await;
+    let _: ActionHash = conductor
+        .call(&bob, "create_message", message("flood 2"))
+        .await;
+    let result: Result<ActionHash, _> = conductor
+        .call_fallible(&bob, "create_message", message("flood 3"))
+        .await;
+    let err = format!("{:?}", result.unwrap_err());
+    assert!(err.contains("more than 2 messages within 60 seconds"));
+
+    // alice's limit is separate from bob's, and only bob's valid messages
+    // reach the feed
+    let _: ActionHash = conductor
+        .call(&alice, "create_message", message("hello"))
+        .await;
+    consistency_10s(cells).await;
+    let messages: Vec<Record> = conductor.call(&alice, "get_messages", ()).await;
+    assert_eq!(messages.len(), 3);
+
+    // peers hold messages committed past their author's own validation to
+    // the same limit: alice's second message is within it, bob's third isn't
+    let unvalidated = |text: &str| HoloMessage {
+        content: MessageContent::Text(text.to_string()),
+        attachment: None,
+        expires_at: None,
+    };
+    let within_limit =
+        commit_unvalidated_message(&conductor, cells[0], unvalidated("hello again")).await;
+    let over_limit = commit_unvalidated_message(&conductor, cells[1], unvalidated("flood 3")).await;
+    consistency_10s(cells).await;
+
+    // only messages that peers hold as valid can be pinned
+    let _: () = conductor.call(&bob, "pin_message", within_limit).await;
+    let result: Result<(), _> = conductor
+        .call_fallible(&alice, "pin_message", over_limit)
+        .await;
+    assert!(result.is_err());
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn expiring_messages() {
+    use hdk::prelude::{ActionHash, Timestamp};
+    use holomessage_integrity::{
+        CreateMessageInput, HoloMessage, MessageContent, MAX_MESSAGE_LIFETIME,
+    };
+    use std::time::Duration;
+
+    let (mut conductor, _, apps) = setup(1).await;
+    let alice = apps[0].cells()[0].zome("holomessage");
+    let message = |expires_in: Duration| CreateMessageInput {
+        content: MessageContent::Text("gone soon".to_string()),
+        attachment: None,
+        expires_at: Some((Timestamp::now() + expires_in).unwrap()),
+    };
+
+    // expiries outside the allowed window are rejected
+    let result: Result<ActionHash, _> = conductor
+        .call_fallible(&alice, "create_message", message(Duration::from_secs(1)))
+        .await;
+    assert!(result.is_err());
+    let result: Result<ActionHash, _> = conductor
+        .call_fallible(
+            &alice,
+            "create_message",
+            message(MAX_MESSAGE_LIFETIME + Duration::from_secs(60)),
+        )
+        .await;
+    assert!(result.is_err());
+
+    let input = message(Duration::from_secs(60 * 60));
+    let _: ActionHash = conductor
+        .call(&alice, "create_message", input.clone())
+        .await;
+    let messages: Vec<Record> = conductor.call(&alice, "get_messages", ()).await;
+    let messages: Vec<HoloMessage> = messages
+        .into_iter()
+        .map(TryFrom::try_from)
+        .flatten()
+        .collect();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].expires_at, input.expires_at);
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn polls() {
+    use hdk::prelude::{ActionHash, Timestamp};
+    use holochain::sweettest::consistency_10s;
+    use holomessage_integrity::{Poll, PollResults, Vote};
+    use std::time::Duration;
+
+    let (mut conductor, _, apps) = setup(2).await;
+    let cells = [&apps[0].cells()[0], &apps[1].cells()[0]];
+    let alice = cells[0].zome("holomessage");
+    let bob = cells[1].zome("holomessage");
+    let poll = |closes_in: Duration| Poll {
+        question: "lunch?".to_string(),
+        options: vec!["pizza".to_string(), "salad".to_string()],
+        closes_at: (Timestamp::now() + closes_in).unwrap(),
+    };
+
+    let poll_hash: ActionHash = conductor
+        .call(&alice, "create_poll", poll(Duration::from_secs(60 * 60)))
+        .await;
+    consistency_10s(cells).await;
+
+    // bob votes once, a second vote and votes for missing options are rejected
+    let vote = |option: u32| Vote {
+        poll: poll_hash.clone(),
+        option,
+    };
+    let _: ActionHash = conductor.call(&bob, "vote", vote(0)).await;
+    let result: Result<ActionHash, _> = conductor.call_fallible(&bob, "vote", vote(1)).await;
+    assert!(result.is_err());
+    let result: Result<ActionHash, _> = conductor.call_fallible(&alice, "vote", vote(2)).await;
+    assert!(result.is_err());
+    let _: ActionHash = conductor.call(&alice, "vote", vote(0)).await;
+    consistency_10s(cells).await;
+
+    let results: PollResults = conductor
+        .call(&alice, "get_poll_results", poll_hash.clone())
+        .await;
+    assert_eq!(results.votes, vec![2, 0]);
+    assert_eq!(results.my_vote, Some(0));
+    let polls: Vec<PollResults> = conductor.call(&bob, "get_polls", ()).await;
+    assert_eq!(
+        polls,
+        vec![PollResults {
+            my_vote: Some(0),
+            ..results
+        }]
+    );
+
+    // no votes after the poll closes
+    let poll_hash: ActionHash = conductor
+        .call(&alice, "create_poll", poll(Duration::from_secs(2)))
+        .await;
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    let result: Result<ActionHash, _> = conductor
+        .call_fallible(
+            &alice,
+            "vote",
+            Vote {
+                poll: poll_hash,
+                option: 0,
+            },
+        )
+        .await;
+    assert!(result.is_err());
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn agent_profiles() {
+    use hc_zome_profiles_integrity::Profile;
+    use holochain::sweettest::consistency_10s;
+    use std::collections::BTreeMap;
+
+    let (mut conductor, agents, apps) = setup(2).await;
+    let alice_profiles = apps[0].cells()[0].zome("profiles");
+    let bob = apps[1].cells()[0].zome("holomessage");
+
+    let _profile: Record = conductor
+        .call(
+            &alice_profiles,
+            "create_profile",
+            Profile {
+                nickname: "alice".to_string(),
+                fields: BTreeMap::new(),
+            },
+        )
+        .await;
+    consistency_10s([&apps[0].cells()[0], &apps[1].cells()[0]]).await;
+
+    // one call resolves the profiles of several agents
+    let profiles: Vec<(AgentPubKey, Option<Record>)> = conductor
+        .call(&bob, "get_agent_profiles", agents.clone())
+        .await;
+    assert_eq!(profiles.len(), 2);
+    assert_eq!(profiles[0].0, agents[0]);
+    let alice_profile = Profile::try_from(profiles[0].1.clone().unwrap()).unwrap();
+    assert_eq!(alice_profile.nickname, "alice");
+    assert_eq!(profiles[1], (agents[1].clone(), None));
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn presence() {
+    use holochain::sweettest::consistency_10s;
+    use holomessage_integrity::{Presence, PresenceStatus};
+    use std::time::Duration;
+
+    let properties = HoloMessageProperties {
+        presence_timeout_seconds: 5,
+        ..Default::default()
+    };
+    let (mut conductor, agents, apps) = setup_with_properties(2, Some(properties)).await;
+    let cells = [&apps[0].cells()[0], &apps[1].cells()[0]];
+    let alice = cells[0].zome("holomessage");
+    let bob = cells[1].zome("holomessage");
+
+    let online: Vec<Presence> = conductor.call(&bob, "get_online_agents", ()).await;
+    assert!(online.is_empty());
+
+    // alice's heartbeats replace each other
+    let _: () = conductor
+        .call(&alice, "announce_presence", PresenceStatus::Online)
+        .await;
+    let _: () = conductor
+        .call(&alice, "announce_presence", PresenceStatus::Away)
+        .await;
+    consistency_10s(cells).await;
+    let online: Vec<Presence> = conductor.call(&bob, "get_online_agents", ()).await;
+    assert_eq!(online.len(), 1);
+    assert_eq!(online[0].agent, agents[0]);
+    assert_eq!(online[0].status, PresenceStatus::Away);
+
+    // without further heartbeats alice goes offline
+    tokio::time::sleep(Duration::from_secs(6)).await;
+    let online: Vec<Presence> = conductor.call(&bob, "get_online_agents", ()).await;
+    assert!(online.is_empty());
+}
+
+#[cfg(test)]
+#[tokio::test(flavor = "multi_thread")]
+async fn message_pages() {
+    use hdk::prelude::ActionHash;
+    use holomessage_integrity::{
+        CreateMessageInput, HoloMessage, MessageContent, MessagesCursor, MessagesPage,
+        MessagesPageInput,
+    };
+
+    let (mut conductor, _, apps) = setup(1).await;
+    let alice = apps[0].cells()[0].zome("holomessage");
+
+    for number in 1..=5 {
+        let _: ActionHash = conductor
+            .call(
+                &alice,
+                "create_message",
+                CreateMessageInput {
+                    content: MessageContent::Text(format!("message {number}")),
+                    attachment: None,
+                    expires_at: None,
+                },
+            )
+            .await;
+    }
+
+    // pages go back in time until the first message
+    let mut texts = Vec::new();
+    let mut before = None;
+    let mut page_sizes = Vec::new();
+    let mut cursors = Vec::new();
+    loop {
+        let page: MessagesPage = conductor
+            .call(
+                &alice,
+                "get_messages_page",
+                MessagesPageInput {
+                    before: before.clone(),
+                    limit: 2,
+                },
+            )
+            .await;
+        cursors.extend(page.older.clone());
+        page_sizes.push(page.records.len());
+        for record in page.records {
+            match HoloMessage::try_from(record).unwrap().content {
+                MessageContent::Text(text) => texts.push(text),
+                content => panic!("unexpected content {content:?}"),
+            }
+        }
+        match page.older {
+            Some(older) => before = Some(older),
+            None => break,
+        }
+    }
+    assert_eq!(page_sizes, vec![2, 2, 1]);
+    assert_eq!(
+        texts,
+        vec![
+            "message 5",
+            "message 4",
+            "message 3",
+            "message 2",
+            "message 1"
+        ]
+    );
+
+    // a message linked at the time of the cursor is still on the next page
+    // when it comes after the cursor's message
+    let page: MessagesPage = conductor
+        .call(
+            &alice,
+            "get_messages_page",
+            MessagesPageInput {
+                before: Some(MessagesCursor {
+                    action_hash: ActionHash::from_raw_36(vec![0xff; 36]),
+                    ..cursors[0].clone()
+                }),
+                limit: 2,
+            },
+        )
+        .await;
+    let texts: Vec<_> = page
+        .records
+        .into_iter()
+        .map(|record| HoloMessage::try_from(record).unwrap().content)
+        .collect();
+    assert_eq!(
+        texts,
+        vec![
+            MessageContent::Text("message 4".to_string()),
+            MessageContent::Text("message 3".to_string())
+        ]
+    );
+}
@@ -0,0 +1,89 @@
+use hdi::prelude::*;
+
+/// Maximum size of a single attachment chunk in bytes.
+pub const ATTACHMENT_CHUNK_SIZE: usize = 256 * 1024;
+/// Maximum size of an attachment in bytes.
+pub const MAX_ATTACHMENT_SIZE: u64 = 16 * 1024 * 1024;
+/// Length of the blake2b content hash of an attachment.
+pub const ATTACHMENT_HASH_LENGTH: u8 = 32;
+
+/// Metadata of a file attached to a message. The content is stored in
+/// `chunks`, in order.
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct Attachment {
+    pub filename: String,
+    pub mime_type: String,
+    pub size: u64,
+    /// blake2b hash of the whole file content
+    pub hash: Vec<u8>,
+    pub chunks: Vec<EntryHash>,
+}
+
+impl Attachment {
+    pub fn is_image(&self) -> bool {
+        self.mime_type.starts_with("image/")
+    }
+}
+
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct AttachmentChunk {
+    #[serde(with = "serde_bytes")]
+    pub data: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UploadFileInput {
+    pub filename: String,
+    pub mime_type: String,
+    pub chunks: Vec<EntryHash>,
+}
+
+pub fn validate_attachment_chunk(chunk: &AttachmentChunk) -> ExternResult<ValidateCallbackResult> {
+    if chunk.data.is_empty() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "attachment chunk is empty".to_string(),
+        ));
+    }
+    if chunk.data.len() > ATTACHMENT_CHUNK_SIZE {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "attachment chunk exceeds {ATTACHMENT_CHUNK_SIZE} bytes"
+        )));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+pub fn validate_attachment(attachment: &Attachment) -> ExternResult<ValidateCallbackResult> {
+    if attachment.size > MAX_ATTACHMENT_SIZE {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "attachment exceeds {MAX_ATTACHMENT_SIZE} bytes"
+        )));
+    }
+    let expected_chunks =
+        (attachment.size as usize + ATTACHMENT_CHUNK_SIZE - 1) / ATTACHMENT_CHUNK_SIZE;
+    if attachment.chunks.len() != expected_chunks {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "attachment of {} bytes must have {expected_chunks} chunks",
+            attachment.size
+        )));
+    }
+
+    let mut content = Vec::with_capacity(attachment.size as usize);
+    for chunk_hash in attachment.chunks.iter() {
+        let entry = must_get_entry(chunk_hash.clone())?.into_content();
+        let chunk = AttachmentChunk::try_from(entry)?;
+        content.extend(chunk.data);
+    }
+    if content.len() as u64 != attachment.size {
+        return Ok(ValidateCallbackResult::Invalid(
+            "attachment size does not match its chunks".to_string(),
+        ));
+    }
+    if hash_blake2b(content, ATTACHMENT_HASH_LENGTH)? != attachment.hash {
+        return Ok(ValidateCallbackResult::Invalid(
+            "attachment hash does not match its chunks".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
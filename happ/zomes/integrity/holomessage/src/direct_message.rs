@@ -0,0 +1,38 @@
+use hdi::prelude::*;
+
+use crate::MessageContent;
+
+/// A one-to-one message. It is committed as a private entry to the source
+/// chains of both sender and recipient and never linked from any public base.
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct DirectMessage {
+    pub from: AgentPubKey,
+    pub to: AgentPubKey,
+    pub content: MessageContent,
+}
+
+impl DirectMessage {
+    /// The other party of the conversation, seen from `me`.
+    pub fn peer(&self, me: &AgentPubKey) -> &AgentPubKey {
+        if &self.from == me {
+            &self.to
+        } else {
+            &self.from
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SendDirectMessageInput {
+    pub to: AgentPubKey,
+    pub content: MessageContent,
+}
+
+/// Result of sending a direct message. The message is always committed to the
+/// sender's chain; `delivered` is false when the recipient couldn't be reached.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DirectMessageSent {
+    pub action_hash: ActionHash,
+    pub delivered: bool,
+}
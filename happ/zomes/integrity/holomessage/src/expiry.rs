@@ -0,0 +1,43 @@
+use hdi::prelude::*;
+
+use crate::HoloMessage;
+
+/// Shortest time a message can be set to live.
+pub const MIN_MESSAGE_LIFETIME: std::time::Duration = std::time::Duration::from_secs(60);
+/// Longest time a message can be set to live.
+pub const MAX_MESSAGE_LIFETIME: std::time::Duration =
+    std::time::Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Why a message with this expiry can't be created at `created_at`, if the
+/// expiry is outside the allowed window.
+pub fn check_message_expiry(expires_at: Timestamp, created_at: Timestamp) -> Option<String> {
+    let lifetime = expires_at.as_micros() - created_at.as_micros();
+    if lifetime < MIN_MESSAGE_LIFETIME.as_micros() as i64 {
+        return Some(format!(
+            "messages must live at least {} seconds",
+            MIN_MESSAGE_LIFETIME.as_secs()
+        ));
+    }
+    if lifetime > MAX_MESSAGE_LIFETIME.as_micros() as i64 {
+        return Some(format!(
+            "messages can live at most {} seconds",
+            MAX_MESSAGE_LIFETIME.as_secs()
+        ));
+    }
+    None
+}
+
+/// A message's expiry is checked against the time of its creation, or of
+/// its update.
+pub fn validate_message_expiry(
+    holo_message: &HoloMessage,
+    created_at: Timestamp,
+) -> ExternResult<ValidateCallbackResult> {
+    let Some(expires_at) = holo_message.expires_at else {
+        return Ok(ValidateCallbackResult::Valid);
+    };
+    match check_message_expiry(expires_at, created_at) {
+        Some(reason) => Ok(ValidateCallbackResult::Invalid(reason)),
+        None => Ok(ValidateCallbackResult::Valid),
+    }
+}
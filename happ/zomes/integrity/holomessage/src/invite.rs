@@ -0,0 +1,86 @@
+use hdi::prelude::*;
+
+use crate::dna_properties;
+
+/// What the inviter signs. Anyone holding the invite may join until it
+/// expires.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InviteContent {
+    pub inviter: AgentPubKey,
+    /// Latest action of the inviter's chain when inviting, so validators can
+    /// check that the inviter's own membership is valid.
+    pub inviter_chain_top: ActionHash,
+    pub expires_at: Timestamp,
+}
+
+/// Invitation to an invite-only network, passed as membrane proof.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, SerializedBytes)]
+pub struct Invite {
+    pub content: InviteContent,
+    pub signature: Signature,
+}
+
+impl Invite {
+    pub fn from_membrane_proof(membrane_proof: &MembraneProof) -> Option<Self> {
+        Invite::try_from((**membrane_proof).clone()).ok()
+    }
+}
+
+/// Checks that don't depend on the network: the invite is present, well
+/// formed and signed by the inviter.
+fn check_invite(
+    agent: &AgentPubKey,
+    membrane_proof: &Option<MembraneProof>,
+) -> ExternResult<Result<Option<Invite>, String>> {
+    let properties = dna_properties()?;
+    if !properties.invite_only || properties.is_progenitor(agent) {
+        return Ok(Ok(None));
+    }
+    let Some(membrane_proof) = membrane_proof else {
+        return Ok(Err("an invite is required to join this network".to_string()));
+    };
+    let Some(invite) = Invite::from_membrane_proof(membrane_proof) else {
+        return Ok(Err("membrane proof is not an invite".to_string()));
+    };
+    if !verify_signature(
+        invite.content.inviter.clone(),
+        invite.signature.clone(),
+        invite.content.clone(),
+    )? {
+        return Ok(Err("invite signature is invalid".to_string()));
+    }
+    Ok(Ok(Some(invite)))
+}
+
+#[hdk_extern]
+pub fn genesis_self_check(data: GenesisSelfCheckData) -> ExternResult<ValidateCallbackResult> {
+    Ok(match check_invite(&data.agent_key, &data.membrane_proof)? {
+        Ok(_) => ValidateCallbackResult::Valid,
+        Err(reason) => ValidateCallbackResult::Invalid(reason),
+    })
+}
+
+/// Full check of the membrane proof of a joining agent: besides the invite
+/// itself, it must not have expired when the agent joined and the inviter's
+/// chain must be valid, which includes the inviter's own membrane proof.
+pub fn validate_agent_validation_pkg(
+    action: &AgentValidationPkg,
+) -> ExternResult<ValidateCallbackResult> {
+    let invite = match check_invite(&action.author, &action.membrane_proof)? {
+        Ok(Some(invite)) => invite,
+        Ok(None) => return Ok(ValidateCallbackResult::Valid),
+        Err(reason) => return Ok(ValidateCallbackResult::Invalid(reason)),
+    };
+    if action.timestamp > invite.content.expires_at {
+        return Ok(ValidateCallbackResult::Invalid(
+            "invite has expired".to_string(),
+        ));
+    }
+    if !dna_properties()?.is_progenitor(&invite.content.inviter) {
+        must_get_agent_activity(
+            invite.content.inviter.clone(),
+            ChainFilter::new(invite.content.inviter_chain_top.clone()),
+        )?;
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
@@ -1,18 +1,297 @@
 use hdi::prelude::*;
 
-#[hdk_entry_helper]
-#[derive(Clone, PartialEq, PartialOrd)]
+mod attachment;
+mod direct_message;
+mod expiry;
+mod invite;
+mod markdown;
+mod mention;
+mod message_content;
+mod message_version;
+mod moderation;
+mod pin;
+mod poll;
+mod presence;
+mod properties;
+mod rate_limit;
+mod read_marker;
+mod signal;
+
+pub use attachment::*;
+pub use direct_message::*;
+pub use expiry::*;
+pub use invite::*;
+pub use markdown::*;
+pub use mention::*;
+pub use message_content::*;
+pub use message_version::*;
+pub use moderation::*;
+pub use pin::*;
+pub use poll::*;
+pub use presence::*;
+pub use properties::*;
+pub use rate_limit::*;
+pub use read_marker::*;
+pub use signal::*;
+
+/// Latest schema of a feed message, stored as `VersionedHoloMessage`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, SerializedBytes)]
 pub struct HoloMessage {
-    pub text: String,
+    pub content: MessageContent,
+    #[serde(default)]
+    pub attachment: Option<EntryHash>,
+    /// When the author's node deletes the message again.
+    #[serde(default)]
+    pub expires_at: Option<Timestamp>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreateMessageInput {
+    pub content: MessageContent,
+    pub attachment: Option<EntryHash>,
+    #[serde(default)]
+    pub expires_at: Option<Timestamp>,
+}
+
+/// Position of a message in the feed: when it was linked and, to order
+/// messages linked at the same time, its hash.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MessagesCursor {
+    pub timestamp: Timestamp,
+    pub action_hash: ActionHash,
+}
+
+/// A page of the feed: the newest `limit` messages linked before `before`,
+/// or the newest overall without it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MessagesPageInput {
+    pub before: Option<MessagesCursor>,
+    pub limit: u32,
+}
+
+/// Messages of a page newest first, and `before` of the next older page if
+/// there is one.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MessagesPage {
+    pub records: Vec<Record>,
+    pub older: Option<MessagesCursor>,
 }
 
 #[hdk_entry_defs]
 #[unit_enum(UnitEntryTypes)]
 pub enum EntryTypes {
-    HoloMessage(HoloMessage),
+    HoloMessage(VersionedHoloMessage),
+    #[entry_def(visibility = "private")]
+    DirectMessage(DirectMessage),
+    #[entry_def(visibility = "private")]
+    ReadMarker(ReadMarker),
+    Attachment(Attachment),
+    AttachmentChunk(AttachmentChunk),
+    ModeratorGrant(ModeratorGrant),
+    Poll(Poll),
+    Vote(Vote),
 }
 
 #[hdk_link_types]
 pub enum LinkTypes {
     HoloMessage,
+    Mention,
+    Pin,
+    Moderator,
+    Hidden,
+    Blocked,
+    Poll,
+    Vote,
+    Presence,
+}
+
+#[hdk_extern]
+pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
+    match op.flattened::<EntryTypes, LinkTypes>()? {
+        FlatOp::StoreRecord(OpRecord::CreateEntry {
+            app_entry: EntryTypes::HoloMessage(holo_message),
+            action,
+        }) => validate_new_message(
+            &holo_message.into_latest(),
+            &action.author,
+            action.timestamp,
+            &action.prev_action,
+        ),
+        FlatOp::StoreRecord(OpRecord::UpdateEntry {
+            app_entry: EntryTypes::HoloMessage(holo_message),
+            action,
+            ..
+        }) => validate_new_message(
+            &holo_message.into_latest(),
+            &action.author,
+            action.timestamp,
+            &action.prev_action,
+        ),
+        FlatOp::StoreRecord(OpRecord::CreateEntry {
+            app_entry: EntryTypes::Poll(poll),
+            action,
+        }) => validate_create_poll(&poll, &action),
+        FlatOp::StoreRecord(OpRecord::CreateEntry {
+            app_entry: EntryTypes::Vote(vote),
+            action,
+        }) => validate_create_vote(&vote, &action),
+        // must_get_valid_record on a grant relies on this check of its record
+        FlatOp::StoreRecord(OpRecord::CreateEntry {
+            app_entry: EntryTypes::ModeratorGrant(_),
+            action,
+        }) => validate_moderator_grant(&action.author),
+        FlatOp::StoreRecord(OpRecord::UpdateEntry {
+            app_entry: EntryTypes::Poll(_) | EntryTypes::Vote(_),
+            ..
+        })
+        | FlatOp::StoreEntry(OpEntry::UpdateEntry {
+            app_entry: EntryTypes::Poll(_) | EntryTypes::Vote(_),
+            ..
+        }) => Ok(ValidateCallbackResult::Invalid(
+            "polls and votes can't be updated".to_string(),
+        )),
+        FlatOp::StoreRecord(OpRecord::DeleteEntry {
+            original_action_hash,
+            action,
+            ..
+        }) => validate_delete_entry(&action, &original_action_hash),
+        FlatOp::RegisterDelete(delete) => {
+            validate_delete_entry(&delete.action, &delete.action.deletes_address)
+        }
+        FlatOp::StoreRecord(OpRecord::UpdateEntry {
+            app_entry: EntryTypes::ModeratorGrant(_),
+            ..
+        })
+        | FlatOp::StoreEntry(OpEntry::UpdateEntry {
+            app_entry: EntryTypes::ModeratorGrant(_),
+            ..
+        }) => Ok(ValidateCallbackResult::Invalid(
+            "moderator grants can't be updated".to_string(),
+        )),
+        FlatOp::StoreEntry(OpEntry::CreateEntry { app_entry, .. })
+        | FlatOp::StoreEntry(OpEntry::UpdateEntry { app_entry, .. }) => match app_entry {
+            EntryTypes::HoloMessage(holo_message) => {
+                validate_message_content(&holo_message.into_latest().content)
+            }
+            EntryTypes::DirectMessage(direct_message) => {
+                validate_message_content(&direct_message.content)
+            }
+            EntryTypes::Attachment(attachment) => validate_attachment(&attachment),
+            EntryTypes::AttachmentChunk(chunk) => validate_attachment_chunk(&chunk),
+            _ => Ok(ValidateCallbackResult::Valid),
+        },
+        FlatOp::RegisterCreateLink {
+            link_type,
+            base_address,
+            target_address,
+            action,
+            ..
+        } => match link_type {
+            LinkTypes::HoloMessage => validate_create_message_link(&action, &target_address),
+            LinkTypes::Mention => {
+                validate_create_mention_link(&action, &base_address, &target_address)
+            }
+            LinkTypes::Pin => validate_create_pin_link(&action, &target_address),
+            LinkTypes::Moderator => validate_create_moderator_link(&action, &target_address),
+            LinkTypes::Hidden => validate_create_hidden_link(&action, &target_address),
+            LinkTypes::Blocked => validate_create_blocked_link(&action, &target_address),
+            LinkTypes::Poll => validate_create_poll_link(&action, &target_address),
+            LinkTypes::Vote => validate_create_vote_link(&action, &base_address, &target_address),
+            LinkTypes::Presence => validate_create_presence_link(&action, &target_address),
+            _ => Ok(ValidateCallbackResult::Valid),
+        },
+        FlatOp::RegisterDeleteLink {
+            link_type,
+            action,
+            original_action,
+            ..
+        } => match link_type {
+            LinkTypes::HoloMessage | LinkTypes::Mention => {
+                validate_delete_message_link(&action, &original_action)
+            }
+            LinkTypes::Pin => validate_delete_pin_link(&action, &original_action),
+            LinkTypes::Moderator => Ok(ValidateCallbackResult::Invalid(
+                "moderator links can't be deleted".to_string(),
+            )),
+            LinkTypes::Hidden | LinkTypes::Blocked => {
+                validate_delete_moderator_link(&action, &original_action)
+            }
+            LinkTypes::Vote => Ok(ValidateCallbackResult::Invalid(
+                "votes can't be withdrawn".to_string(),
+            )),
+            LinkTypes::Presence => validate_delete_presence_link(&action, &original_action),
+            _ => Ok(ValidateCallbackResult::Valid),
+        },
+        FlatOp::RegisterAgentActivity(OpActivity::AgentValidationPkg { action, .. }) => {
+            validate_agent_validation_pkg(&action)
+        }
+        _ => Ok(ValidateCallbackResult::Valid),
+    }
+}
+
+/// Expiry and rate limit of a message created or updated by `author`.
+fn validate_new_message(
+    holo_message: &HoloMessage,
+    author: &AgentPubKey,
+    timestamp: Timestamp,
+    prev_action: &ActionHash,
+) -> ExternResult<ValidateCallbackResult> {
+    match validate_message_expiry(holo_message, timestamp)? {
+        ValidateCallbackResult::Valid => validate_message_rate(author, timestamp, prev_action),
+        invalid => Ok(invalid),
+    }
+}
+
+/// Feed links go from the author's key or the path of all messages to a
+/// message, created by its author.
+fn validate_create_message_link(
+    action: &CreateLink,
+    target_address: &AnyLinkableHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let Some(message_hash) = target_address.clone().into_action_hash() else {
+        return Ok(ValidateCallbackResult::Invalid(
+            "message link must point to a message".to_string(),
+        ));
+    };
+    // the message record is only valid within the rate limit
+    let record = must_get_valid_record(message_hash)?;
+    if HoloMessage::try_from(&record).is_err() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "message link must point to a message".to_string(),
+        ));
+    }
+    if record.action().author() != &action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "only the author of a message may link it".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Entries, such as expired messages, are only deleted by their author.
+fn validate_delete_entry(
+    action: &Delete,
+    original_action_hash: &ActionHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let original_action = must_get_action(original_action_hash.clone())?;
+    if original_action.action().author() != &action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "only the author can delete an entry".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Feed and mention links of a message are only deleted by its author, who
+/// created them.
+fn validate_delete_message_link(
+    action: &DeleteLink,
+    original_action: &CreateLink,
+) -> ExternResult<ValidateCallbackResult> {
+    if action.author != original_action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "only the author can delete a message link".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
 }
@@ -0,0 +1,77 @@
+/// Maximum length of a message text in bytes.
+pub const MAX_MESSAGE_TEXT_LENGTH: usize = 8 * 1024;
+/// Maximum nesting of block quotes and lists in a message text.
+pub const MAX_MARKDOWN_NESTING: usize = 8;
+/// Maximum size of a sealed message text, a crypto box adds 16 bytes.
+pub(crate) const MAX_SEALED_TEXT_LENGTH: usize = MAX_MESSAGE_TEXT_LENGTH + 16;
+
+/// Deepest nesting of block quotes and list items in a CommonMark text.
+///
+/// Nesting is counted per line from `>` quote markers, list markers and the
+/// indentation in front of them, lines of fenced code blocks are skipped. This
+/// overestimates rather than underestimates what a CommonMark parser builds.
+pub fn markdown_nesting_depth(text: &str) -> usize {
+    let mut max_depth = 0;
+    let mut in_fence = false;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let mut depth = 0;
+        let mut rest = line;
+        loop {
+            let trimmed = rest.trim_start();
+            let indent = rest.len() - trimmed.len();
+            if let Some(after_quote) = trimmed.strip_prefix('>') {
+                depth += 1 + indent / 2;
+                rest = after_quote;
+            } else if let Some(after_marker) = strip_list_marker(trimmed) {
+                depth += 1 + indent / 2;
+                rest = after_marker;
+            } else {
+                break;
+            }
+        }
+        max_depth = max_depth.max(depth);
+    }
+    max_depth
+}
+
+/// The rest of a line after a bullet (`-`, `*`, `+`) or ordered (`1.`, `1)`)
+/// list marker.
+fn strip_list_marker(line: &str) -> Option<&str> {
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let marker_len = match line[digits..].chars().next()? {
+        '-' | '*' | '+' if digits == 0 => 1,
+        '.' | ')' if digits > 0 && digits <= 9 => digits + 1,
+        _ => return None,
+    };
+    let rest = &line[marker_len..];
+    if rest.is_empty() || rest.starts_with(' ') || rest.starts_with('\t') {
+        Some(rest)
+    } else {
+        None
+    }
+}
+
+/// Why a message text can't be stored, if it exceeds the size or nesting
+/// limits.
+pub fn check_message_text(text: &str) -> Option<String> {
+    if text.len() > MAX_MESSAGE_TEXT_LENGTH {
+        return Some(format!(
+            "message text exceeds {MAX_MESSAGE_TEXT_LENGTH} bytes"
+        ));
+    }
+    if markdown_nesting_depth(text) > MAX_MARKDOWN_NESTING {
+        return Some(format!(
+            "message text nests quotes and lists deeper than {MAX_MARKDOWN_NESTING} levels"
+        ));
+    }
+    None
+}
@@ -0,0 +1,106 @@
+use hdi::prelude::*;
+
+use crate::{HoloMessage, MessageContent};
+
+/// Nicknames mentioned as `@nickname` in a message text, without duplicates.
+pub fn mentioned_nicknames(text: &str) -> Vec<&str> {
+    let mut nicknames = Vec::new();
+    for word in text.split_whitespace() {
+        let Some(mention) = word.strip_prefix('@') else {
+            continue;
+        };
+        let nickname =
+            mention.trim_end_matches(|c: char| !c.is_alphanumeric() && c != '_' && c != '-');
+        if !nickname.is_empty() && !nicknames.contains(&nickname) {
+            nicknames.push(nickname);
+        }
+    }
+    nicknames
+}
+
+/// Tag of a mention link, with the record of the mentioned agent's profile
+/// that has the nickname mentioned.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, SerializedBytes)]
+pub struct MentionTag {
+    pub profile: ActionHash,
+}
+
+impl MentionTag {
+    pub fn to_link_tag(&self) -> ExternResult<LinkTag> {
+        let bytes = SerializedBytes::try_from(self.clone()).map_err(|err| wasm_error!(err))?;
+        Ok(LinkTag::new(bytes.bytes().clone()))
+    }
+
+    pub fn from_link_tag(tag: &LinkTag) -> ExternResult<Self> {
+        MentionTag::try_from(SerializedBytes::from(UnsafeBytes::from(tag.0.clone())))
+            .map_err(|err| wasm_error!(err))
+    }
+}
+
+/// The part of a profile entry of the profiles zome that mentions refer to.
+#[derive(Serialize, Deserialize, Debug, SerializedBytes)]
+struct ProfileNickname {
+    nickname: String,
+}
+
+/// Mention links go from an agent to a message that mentions the agent's
+/// nickname, and are created by the message's author.
+pub fn validate_create_mention_link(
+    action: &CreateLink,
+    base_address: &AnyLinkableHash,
+    target_address: &AnyLinkableHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let Some(message_hash) = target_address.clone().into_action_hash() else {
+        return Ok(ValidateCallbackResult::Invalid(
+            "mention link must point to a message".to_string(),
+        ));
+    };
+    let message_record = must_get_valid_record(message_hash)?;
+    let Ok(holo_message) = HoloMessage::try_from(&message_record) else {
+        return Ok(ValidateCallbackResult::Invalid(
+            "mention link must point to a message".to_string(),
+        ));
+    };
+    if message_record.action().author() != &action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "only the author of a message may link its mentions".to_string(),
+        ));
+    }
+
+    let Some(agent) = base_address.clone().into_agent_pub_key() else {
+        return Ok(ValidateCallbackResult::Invalid(
+            "mention link must start from an agent".to_string(),
+        ));
+    };
+    let Ok(tag) = MentionTag::from_link_tag(&action.tag) else {
+        return Ok(ValidateCallbackResult::Invalid(
+            "mention link tag is not a mention tag".to_string(),
+        ));
+    };
+    let profile_record = must_get_valid_record(tag.profile)?;
+    let profile = profile_record
+        .entry()
+        .as_option()
+        .and_then(|entry| match entry {
+            Entry::App(bytes) => ProfileNickname::try_from(bytes.clone().into_sb()).ok(),
+            _ => None,
+        });
+    let Some(profile) = profile.filter(|_| profile_record.action().author() == &agent) else {
+        return Ok(ValidateCallbackResult::Invalid(
+            "mention link tag must hold a profile of the mentioned agent".to_string(),
+        ));
+    };
+
+    // mentions can only be resolved in readable messages
+    let MessageContent::Text(text) = &holo_message.content else {
+        return Ok(ValidateCallbackResult::Invalid(
+            "encrypted messages can't mention agents".to_string(),
+        ));
+    };
+    if !mentioned_nicknames(text).contains(&profile.nickname.as_str()) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "message doesn't mention the agent".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
@@ -0,0 +1,55 @@
+use hdi::prelude::*;
+
+use crate::{check_message_text, markdown::MAX_SEALED_TEXT_LENGTH};
+
+/// Payload of a feed or direct message.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum MessageContent {
+    Text(String),
+    Encrypted(EncryptedContent),
+}
+
+/// Text sealed with an x25519 crypto box once per recipient. Keys and nonces
+/// are kept as plain bytes, sealing and opening happen in the client through
+/// the conductor's keystore.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EncryptedContent {
+    pub sender_key: [u8; 32],
+    pub boxes: Vec<SealedBox>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SealedBox {
+    pub recipient_key: [u8; 32],
+    pub nonce: [u8; 24],
+    pub cipher: Vec<u8>,
+}
+
+impl MessageContent {
+    pub fn is_encrypted(&self) -> bool {
+        matches!(self, MessageContent::Encrypted(_))
+    }
+}
+
+/// Plain texts are checked against the size and markdown nesting limits.
+/// Sealed texts can only be checked for size.
+pub fn validate_message_content(content: &MessageContent) -> ExternResult<ValidateCallbackResult> {
+    match content {
+        MessageContent::Text(text) => Ok(match check_message_text(text) {
+            Some(reason) => ValidateCallbackResult::Invalid(reason),
+            None => ValidateCallbackResult::Valid,
+        }),
+        MessageContent::Encrypted(encrypted_content) => {
+            if encrypted_content
+                .boxes
+                .iter()
+                .any(|sealed_box| sealed_box.cipher.len() > MAX_SEALED_TEXT_LENGTH)
+            {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "sealed message text is too long".to_string(),
+                ));
+            }
+            Ok(ValidateCallbackResult::Valid)
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use hdi::prelude::*;
+
+use crate::{HoloMessage, MessageContent};
+
+/// Feed message as stored in the DHT, tagged with the version of its schema.
+///
+/// New fields are added to `HoloMessage` with a serde default. Changes that
+/// can't be expressed that way get a new variant, along with a conversion of
+/// older variants into the latest `HoloMessage`. Readers decode every variant,
+/// see `HoloMessage::try_from`.
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+#[serde(tag = "version")]
+pub enum VersionedHoloMessage {
+    #[serde(rename = "2")]
+    V2(HoloMessage),
+}
+
+impl VersionedHoloMessage {
+    pub fn into_latest(self) -> HoloMessage {
+        match self {
+            VersionedHoloMessage::V2(holo_message) => holo_message,
+        }
+    }
+}
+
+/// Shapes written before messages were versioned, found when migrating data
+/// out of an older DNA.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, SerializedBytes)]
+#[serde(untagged)]
+enum LegacyHoloMessage {
+    /// Messages with content, attachment and expiry fields but no version.
+    Unversioned(HoloMessage),
+    /// The first version, a plain text.
+    Text { text: String },
+}
+
+impl From<LegacyHoloMessage> for HoloMessage {
+    fn from(legacy: LegacyHoloMessage) -> Self {
+        match legacy {
+            LegacyHoloMessage::Unversioned(holo_message) => holo_message,
+            LegacyHoloMessage::Text { text } => HoloMessage {
+                content: MessageContent::Text(text),
+                attachment: None,
+                expires_at: None,
+            },
+        }
+    }
+}
+
+/// Decodes versioned messages as well as the shapes written before messages
+/// were versioned.
+impl TryFrom<&Entry> for HoloMessage {
+    type Error = WasmError;
+
+    fn try_from(entry: &Entry) -> Result<Self, Self::Error> {
+        let Entry::App(AppEntryBytes(bytes)) = entry else {
+            return Err(wasm_error!(WasmErrorInner::Guest(
+                "message must be an app entry".to_string()
+            )));
+        };
+        if let Ok(versioned) = VersionedHoloMessage::try_from(bytes.clone()) {
+            return Ok(versioned.into_latest());
+        }
+        LegacyHoloMessage::try_from(bytes.clone())
+            .map(HoloMessage::from)
+            .map_err(|err| wasm_error!(err))
+    }
+}
+
+impl TryFrom<Entry> for HoloMessage {
+    type Error = WasmError;
+
+    fn try_from(entry: Entry) -> Result<Self, Self::Error> {
+        HoloMessage::try_from(&entry)
+    }
+}
+
+impl TryFrom<&Record> for HoloMessage {
+    type Error = WasmError;
+
+    fn try_from(record: &Record) -> Result<Self, Self::Error> {
+        match record.entry().as_option() {
+            Some(entry) => HoloMessage::try_from(entry),
+            None => Err(wasm_error!(WasmErrorInner::Guest(format!(
+                "record {} has no message entry",
+                record.action_address()
+            )))),
+        }
+    }
+}
+
+impl TryFrom<Record> for HoloMessage {
+    type Error = WasmError;
+
+    fn try_from(record: Record) -> Result<Self, Self::Error> {
+        HoloMessage::try_from(&record)
+    }
+}
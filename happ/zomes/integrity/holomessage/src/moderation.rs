@@ -0,0 +1,155 @@
+use hdi::prelude::*;
+
+use crate::{dna_properties, HoloMessage};
+
+/// Moderator status granted by the progenitor. Grants can't be updated or
+/// revoked.
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct ModeratorGrant {
+    pub agent: AgentPubKey,
+}
+
+/// Carried in the tag of moderator links to prove the author's role: the
+/// grant of the author, or none if the author is the progenitor.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, SerializedBytes)]
+pub struct ModeratorProof {
+    pub grant: Option<ActionHash>,
+}
+
+impl ModeratorProof {
+    pub fn to_link_tag(&self) -> ExternResult<LinkTag> {
+        let bytes = SerializedBytes::try_from(self.clone()).map_err(|err| wasm_error!(err))?;
+        Ok(LinkTag::new(bytes.bytes().clone()))
+    }
+
+    pub fn from_link_tag(tag: &LinkTag) -> ExternResult<Self> {
+        ModeratorProof::try_from(SerializedBytes::from(UnsafeBytes::from(tag.0.clone())))
+            .map_err(|err| wasm_error!(err))
+    }
+}
+
+/// Moderators, hidden messages and blocked agents of the network.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Moderation {
+    pub moderators: Vec<AgentPubKey>,
+    pub hidden_messages: Vec<ActionHash>,
+    pub blocked_agents: Vec<AgentPubKey>,
+}
+
+/// Why `author` isn't a moderator according to the proof in `tag`, if it
+/// isn't.
+pub fn check_moderator(author: &AgentPubKey, tag: &LinkTag) -> ExternResult<Option<String>> {
+    let Ok(proof) = ModeratorProof::from_link_tag(tag) else {
+        return Ok(Some("link tag is not a moderator proof".to_string()));
+    };
+    let Some(grant_hash) = proof.grant else {
+        return Ok((!dna_properties()?.is_progenitor(author))
+            .then(|| "author is not the progenitor".to_string()));
+    };
+    // the grant record has been validated to be authored by the progenitor
+    let grant = ModeratorGrant::try_from(must_get_valid_record(grant_hash)?);
+    Ok(match grant {
+        Ok(grant) if &grant.agent == author => None,
+        Ok(_) => Some("moderator grant is for another agent".to_string()),
+        Err(_) => Some("moderator proof does not point to a grant".to_string()),
+    })
+}
+
+pub fn validate_moderator_grant(author: &AgentPubKey) -> ExternResult<ValidateCallbackResult> {
+    if !dna_properties()?.is_progenitor(author) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "only the progenitor may grant moderator status".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Links from the moderators path to grants, for finding all moderators.
+pub fn validate_create_moderator_link(
+    action: &CreateLink,
+    target_address: &AnyLinkableHash,
+) -> ExternResult<ValidateCallbackResult> {
+    if !dna_properties()?.is_progenitor(&action.author) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "only the progenitor may link moderator grants".to_string(),
+        ));
+    }
+    let Some(grant_hash) = target_address.clone().into_action_hash() else {
+        return Ok(ValidateCallbackResult::Invalid(
+            "moderator link must point to a grant action".to_string(),
+        ));
+    };
+    if ModeratorGrant::try_from(must_get_valid_record(grant_hash)?).is_err() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "moderator link must point to a grant".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Whether a link target is a message action.
+pub(crate) fn is_message(target_address: &AnyLinkableHash) -> ExternResult<bool> {
+    match target_address.clone().into_action_hash() {
+        Some(message_hash) => {
+            Ok(HoloMessage::try_from(must_get_valid_record(message_hash)?).is_ok())
+        }
+        None => Ok(false),
+    }
+}
+
+/// Hidden links go from the feed path to a message. Their tag proves the
+/// author is a moderator.
+pub fn validate_create_hidden_link(
+    action: &CreateLink,
+    target_address: &AnyLinkableHash,
+) -> ExternResult<ValidateCallbackResult> {
+    if let Some(reason) = check_moderator(&action.author, &action.tag)? {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "only moderators may hide messages: {reason}"
+        )));
+    }
+    if !is_message(target_address)? {
+        return Ok(ValidateCallbackResult::Invalid(
+            "hidden link must point to a message".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Blocked links go from the blocked agents path to an agent. Their tag
+/// proves the author is a moderator.
+///
+/// Blocking is not enforced by validation, the links can't be looked up
+/// deterministically when validating an agent's messages. Clients hide the
+/// messages of blocked agents instead.
+pub fn validate_create_blocked_link(
+    action: &CreateLink,
+    target_address: &AnyLinkableHash,
+) -> ExternResult<ValidateCallbackResult> {
+    if let Some(reason) = check_moderator(&action.author, &action.tag)? {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "only moderators may block agents: {reason}"
+        )));
+    }
+    if target_address.clone().into_agent_pub_key().is_none() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "blocked link must point to an agent".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// A delete link action carries no moderator proof, so links created by a
+/// moderator can only be deleted by that moderator or the progenitor.
+pub fn validate_delete_moderator_link(
+    action: &DeleteLink,
+    original_action: &CreateLink,
+) -> ExternResult<ValidateCallbackResult> {
+    if action.author != original_action.author && !dna_properties()?.is_progenitor(&action.author) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "only the progenitor or the moderator who created it may delete this link".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
@@ -0,0 +1,37 @@
+use hdi::prelude::*;
+
+use crate::{
+    check_moderator, dna_properties, moderation::is_message, validate_delete_moderator_link,
+    PinPermission,
+};
+
+/// Pin links go from the feed path to a message. If only moderators may pin,
+/// their tag carries a moderator proof.
+pub fn validate_create_pin_link(
+    action: &CreateLink,
+    target_address: &AnyLinkableHash,
+) -> ExternResult<ValidateCallbackResult> {
+    if dna_properties()?.pin_permission == PinPermission::Moderators {
+        if let Some(reason) = check_moderator(&action.author, &action.tag)? {
+            return Ok(ValidateCallbackResult::Invalid(format!(
+                "only moderators may pin messages: {reason}"
+            )));
+        }
+    }
+    if !is_message(target_address)? {
+        return Ok(ValidateCallbackResult::Invalid(
+            "pin link must point to a message".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+pub fn validate_delete_pin_link(
+    action: &DeleteLink,
+    original_action: &CreateLink,
+) -> ExternResult<ValidateCallbackResult> {
+    match dna_properties()?.pin_permission {
+        PinPermission::Anyone => Ok(ValidateCallbackResult::Valid),
+        PinPermission::Moderators => validate_delete_moderator_link(action, original_action),
+    }
+}
@@ -0,0 +1,182 @@
+use hdi::prelude::*;
+
+use crate::{rate_limit::ACTIVITY_BATCH, UnitEntryTypes};
+
+/// Maximum number of options of a poll.
+pub const MAX_POLL_OPTIONS: usize = 10;
+/// Maximum length of a poll's question and of each option in bytes.
+pub const MAX_POLL_TEXT_LENGTH: usize = 256;
+
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct Poll {
+    pub question: String,
+    pub options: Vec<String>,
+    /// Votes are accepted until this point in time.
+    pub closes_at: Timestamp,
+}
+
+/// An agent's vote for an option of a poll, by index. Every agent votes once
+/// per poll and can't change their vote.
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct Vote {
+    pub poll: ActionHash,
+    pub option: u32,
+}
+
+/// A poll of the feed with the votes counted so far.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PollResults {
+    pub poll_hash: ActionHash,
+    pub author: AgentPubKey,
+    pub created_at: Timestamp,
+    pub poll: Poll,
+    /// Number of votes per option.
+    pub votes: Vec<u32>,
+    /// Option this agent voted for.
+    pub my_vote: Option<u32>,
+}
+
+/// Why a poll can't be created at `created_at`, if it is malformed.
+pub fn check_poll(poll: &Poll, created_at: Timestamp) -> Option<String> {
+    if poll.question.trim().is_empty() {
+        return Some("poll has no question".to_string());
+    }
+    if poll.options.len() < 2 || poll.options.len() > MAX_POLL_OPTIONS {
+        return Some(format!(
+            "poll must have between 2 and {MAX_POLL_OPTIONS} options"
+        ));
+    }
+    if poll.options.iter().any(|option| option.trim().is_empty()) {
+        return Some("poll options can't be empty".to_string());
+    }
+    if std::iter::once(&poll.question)
+        .chain(poll.options.iter())
+        .any(|text| text.len() > MAX_POLL_TEXT_LENGTH)
+    {
+        return Some(format!(
+            "poll texts can't exceed {MAX_POLL_TEXT_LENGTH} bytes"
+        ));
+    }
+    if poll.closes_at <= created_at {
+        return Some("poll must close in the future".to_string());
+    }
+    None
+}
+
+pub fn validate_create_poll(poll: &Poll, action: &Create) -> ExternResult<ValidateCallbackResult> {
+    match check_poll(poll, action.timestamp) {
+        Some(reason) => Ok(ValidateCallbackResult::Invalid(reason)),
+        None => Ok(ValidateCallbackResult::Valid),
+    }
+}
+
+/// A vote must be for an option of an open poll, and the author must not have
+/// voted in that poll before.
+pub fn validate_create_vote(vote: &Vote, action: &Create) -> ExternResult<ValidateCallbackResult> {
+    let poll_record = must_get_valid_record(vote.poll.clone())?;
+    let Ok(poll) = Poll::try_from(&poll_record) else {
+        return Ok(ValidateCallbackResult::Invalid(
+            "vote must be for a poll".to_string(),
+        ));
+    };
+    if vote.option as usize >= poll.options.len() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "vote is for an option the poll doesn't have".to_string(),
+        ));
+    }
+    if action.timestamp >= poll.closes_at {
+        return Ok(ValidateCallbackResult::Invalid(
+            "poll is closed".to_string(),
+        ));
+    }
+
+    // earlier votes can only have been cast after the poll was created
+    let poll_created_at = poll_record.action().timestamp();
+    let vote_type: EntryType = UnitEntryTypes::Vote.try_into()?;
+    let mut chain_top = Some(action.prev_action.clone());
+    // newest first, a batch at a time until the poll or the chain starts
+    'chain: while let Some(batch_top) = chain_top.take() {
+        let activity = must_get_agent_activity(
+            action.author.clone(),
+            ChainFilter::new(batch_top).take(ACTIVITY_BATCH),
+        )?;
+        for register_activity in activity.iter() {
+            let previous_action = register_activity.action.action();
+            if previous_action.timestamp() < poll_created_at {
+                break 'chain;
+            }
+            chain_top = previous_action.prev_action().cloned();
+            if previous_action.action_type() != ActionType::Create
+                || previous_action.entry_type() != Some(&vote_type)
+            {
+                continue;
+            }
+            let Some(entry_hash) = previous_action.entry_hash() else {
+                continue;
+            };
+            let previous_vote = Vote::try_from(must_get_entry(entry_hash.clone())?.content)?;
+            if previous_vote.poll == vote.poll {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "agent has already voted in this poll".to_string(),
+                ));
+            }
+        }
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Poll links go from the feed path to a poll, created by its author.
+pub fn validate_create_poll_link(
+    action: &CreateLink,
+    target_address: &AnyLinkableHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let Some(poll_hash) = target_address.clone().into_action_hash() else {
+        return Ok(ValidateCallbackResult::Invalid(
+            "poll link must point to a poll".to_string(),
+        ));
+    };
+    let poll_record = must_get_valid_record(poll_hash)?;
+    if Poll::try_from(&poll_record).is_err() {
+        return Ok(ValidateCallbackResult::Invalid(
+            "poll link must point to a poll".to_string(),
+        ));
+    }
+    if poll_record.action().author() != &action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "only the author of a poll may link it".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// Vote links go from a poll to a vote for it, created by the voter.
+pub fn validate_create_vote_link(
+    action: &CreateLink,
+    base_address: &AnyLinkableHash,
+    target_address: &AnyLinkableHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let Some(vote_hash) = target_address.clone().into_action_hash() else {
+        return Ok(ValidateCallbackResult::Invalid(
+            "vote link must point to a vote".to_string(),
+        ));
+    };
+    let vote_record = must_get_valid_record(vote_hash)?;
+    let Ok(vote) = Vote::try_from(&vote_record) else {
+        return Ok(ValidateCallbackResult::Invalid(
+            "vote link must point to a vote".to_string(),
+        ));
+    };
+    if AnyLinkableHash::from(vote.poll) != *base_address {
+        return Ok(ValidateCallbackResult::Invalid(
+            "vote link must go from the poll voted in".to_string(),
+        ));
+    }
+    if vote_record.action().author() != &action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "only the voter may link a vote".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
@@ -0,0 +1,74 @@
+use hdi::prelude::*;
+
+/// Path that presence links start from.
+pub const PRESENCE_PATH: &str = "presence";
+
+/// What an agent announces in its heartbeats. Agents without a recent
+/// heartbeat are offline.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresenceStatus {
+    Online,
+    Away,
+}
+
+/// Tag of a presence link from the presence path to the announcing agent.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, SerializedBytes)]
+pub struct PresenceTag {
+    pub status: PresenceStatus,
+    pub timestamp: Timestamp,
+}
+
+impl PresenceTag {
+    pub fn to_link_tag(&self) -> ExternResult<LinkTag> {
+        let bytes = SerializedBytes::try_from(self.clone()).map_err(|err| wasm_error!(err))?;
+        Ok(LinkTag::new(bytes.bytes().clone()))
+    }
+
+    pub fn from_link_tag(tag: &LinkTag) -> ExternResult<Self> {
+        PresenceTag::try_from(SerializedBytes::from(UnsafeBytes::from(tag.0.clone())))
+            .map_err(|err| wasm_error!(err))
+    }
+}
+
+/// Latest heartbeat of an agent that is online or away.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Presence {
+    pub agent: AgentPubKey,
+    pub status: PresenceStatus,
+    pub last_seen: Timestamp,
+}
+
+/// Agents only announce their own presence, and not ahead of time.
+pub fn validate_create_presence_link(
+    action: &CreateLink,
+    target_address: &AnyLinkableHash,
+) -> ExternResult<ValidateCallbackResult> {
+    if target_address != &AnyLinkableHash::from(action.author.clone()) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "presence link must point to its author".to_string(),
+        ));
+    }
+    let Ok(tag) = PresenceTag::from_link_tag(&action.tag) else {
+        return Ok(ValidateCallbackResult::Invalid(
+            "presence link tag is not a presence tag".to_string(),
+        ));
+    };
+    if tag.timestamp > action.timestamp {
+        return Ok(ValidateCallbackResult::Invalid(
+            "presence can't be announced for the future".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+pub fn validate_delete_presence_link(
+    action: &DeleteLink,
+    original_action: &CreateLink,
+) -> ExternResult<ValidateCallbackResult> {
+    if action.author != original_action.author {
+        return Ok(ValidateCallbackResult::Invalid(
+            "only the author can delete a presence link".to_string(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
@@ -0,0 +1,61 @@
+use hdi::prelude::*;
+
+use crate::RateLimit;
+
+/// Typed DNA properties, set under `properties` in `dna.yaml`. A DNA with
+/// `properties: ~` gets the defaults.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, SerializedBytes)]
+#[serde(default)]
+pub struct HoloMessageProperties {
+    /// Agent who started the network. The progenitor is a moderator and
+    /// grants moderator status to others. Base64 encoded so it can be written
+    /// in `dna.yaml`.
+    pub progenitor: Option<AgentPubKeyB64>,
+    pub pin_permission: PinPermission,
+    /// Agents other than the progenitor need an invite from a member to join.
+    pub invite_only: bool,
+    /// Feed messages an agent may create per period, `None` for no limit.
+    pub message_rate_limit: Option<RateLimit>,
+    /// Seconds after their last heartbeat that agents count as offline.
+    pub presence_timeout_seconds: u64,
+}
+
+impl Default for HoloMessageProperties {
+    fn default() -> Self {
+        HoloMessageProperties {
+            progenitor: None,
+            pin_permission: PinPermission::default(),
+            invite_only: false,
+            message_rate_limit: Some(RateLimit::default()),
+            presence_timeout_seconds: 15 * 60,
+        }
+    }
+}
+
+/// Who may pin and unpin messages of the feed.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PinPermission {
+    #[default]
+    Anyone,
+    Moderators,
+}
+
+impl HoloMessageProperties {
+    pub fn is_progenitor(&self, agent: &AgentPubKey) -> bool {
+        self.progenitor.as_ref().map_or(false, |progenitor| {
+            &AgentPubKey::from(progenitor.clone()) == agent
+        })
+    }
+}
+
+/// msgpack encoding of `~`
+const NIL_PROPERTIES: [u8; 1] = [0xc0];
+
+pub fn dna_properties() -> ExternResult<HoloMessageProperties> {
+    let properties = dna_info()?.modifiers.properties;
+    if properties.bytes().as_slice() == NIL_PROPERTIES {
+        return Ok(HoloMessageProperties::default());
+    }
+    HoloMessageProperties::try_from(properties).map_err(|err| wasm_error!(err))
+}
@@ -0,0 +1,76 @@
+use hdi::prelude::*;
+
+use crate::{dna_properties, UnitEntryTypes};
+
+/// Actions fetched at once when walking an author's chain back to a point in
+/// time, see `validate_message_rate`.
+pub(crate) const ACTIVITY_BATCH: u32 = 100;
+
+/// How many feed messages an agent may create within a period.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub max_messages: u32,
+    pub period_seconds: u64,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        RateLimit {
+            max_messages: 10,
+            period_seconds: 60,
+        }
+    }
+}
+
+/// Reject a message written at `timestamp` if its author already created or
+/// updated the maximum number of messages within the period before it.
+///
+/// The chain is walked back from `prev_action` until the start of the
+/// period, however many other actions the author committed in between.
+pub fn validate_message_rate(
+    author: &AgentPubKey,
+    timestamp: Timestamp,
+    prev_action: &ActionHash,
+) -> ExternResult<ValidateCallbackResult> {
+    let Some(rate_limit) = dna_properties()?.message_rate_limit else {
+        return Ok(ValidateCallbackResult::Valid);
+    };
+    let period_start = (timestamp - std::time::Duration::from_secs(rate_limit.period_seconds))
+        .map_err(|err| wasm_error!(WasmErrorInner::Guest(err.to_string())))?;
+
+    let holo_message_type: EntryType = UnitEntryTypes::HoloMessage.try_into()?;
+    let mut messages = 0;
+    let mut chain_top = Some(prev_action.clone());
+    // newest first, a batch at a time until the period or the chain starts
+    'chain: while let Some(batch_top) = chain_top.take() {
+        if messages >= rate_limit.max_messages {
+            break;
+        }
+        let activity = must_get_agent_activity(
+            author.clone(),
+            ChainFilter::new(batch_top).take(ACTIVITY_BATCH),
+        )?;
+        for register_activity in activity.iter() {
+            let previous_action = register_activity.action.action();
+            if previous_action.timestamp() < period_start {
+                break 'chain;
+            }
+            if matches!(
+                previous_action.action_type(),
+                ActionType::Create | ActionType::Update
+            ) && previous_action.entry_type() == Some(&holo_message_type)
+            {
+                messages += 1;
+            }
+            chain_top = previous_action.prev_action().cloned();
+        }
+    }
+
+    if messages >= rate_limit.max_messages {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "more than {} messages within {} seconds",
+            rate_limit.max_messages, rate_limit.period_seconds
+        )));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
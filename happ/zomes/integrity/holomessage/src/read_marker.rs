@@ -0,0 +1,14 @@
+use hdi::prelude::*;
+
+/// Channel name of the shared message feed.
+pub const FEED_CHANNEL: &str = "all_messages";
+
+/// The last message an agent has seen in a channel. Read markers are private
+/// entries, the most recent marker per channel is the one that counts.
+#[hdk_entry_helper]
+#[derive(Clone, PartialEq)]
+pub struct ReadMarker {
+    pub channel: String,
+    pub last_seen: ActionHash,
+    pub last_seen_at: Timestamp,
+}
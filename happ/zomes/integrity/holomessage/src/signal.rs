@@ -0,0 +1,33 @@
+use hdi::prelude::*;
+
+use crate::DirectMessage;
+
+/// Signals sent between agents with `send_remote_signal`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum RemoteSignal {
+    Typing { nickname: String },
+    ProfileUpdated,
+}
+
+/// Signals emitted by the holomessage coordinator zome to the local client.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum HoloMessageSignal {
+    Typing {
+        agent: AgentPubKey,
+        nickname: String,
+    },
+    DirectMessageReceived {
+        action_hash: ActionHash,
+        timestamp: Timestamp,
+        direct_message: DirectMessage,
+    },
+    ProfileUpdated {
+        agent: AgentPubKey,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NotifyTypingInput {
+    pub agents: Vec<AgentPubKey>,
+    pub nickname: String,
+}
@@ -1,6 +1,18 @@
+use crate::{
+    migration::migrated_messages,
+    profile::{
+        check_nickname_length, suggest_nicknames, NicknameProblem, ProfileDetails, MAX_BIO_LENGTH,
+    },
+    search::{IndexedMessage, SearchHit, SearchIndex, SearchQuery},
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use futures::{Stream, StreamExt};
 use get_port::Ops;
 use hc_zome_profiles_integrity::Profile;
-use hdk::prelude::{ActionHash, CellId, ExternIO, FunctionName, Record, Timestamp, ZomeName};
+use hdk::prelude::{
+    ActionHash, AgentPubKeyB64, CellId, EntryHash, ExternIO, FunctionName, Link, Record, Timestamp,
+    ZomeName,
+};
 use holochain::{
     conductor::{
         api::CellInfo,
@@ -10,22 +22,101 @@ use holochain::{
         Conductor, ConductorBuilder,
     },
     prelude::{
-        kitsune_p2p::dependencies::url2::url2, AppBundleSource, KitsuneP2pConfig, TransportConfig,
-        ZomeCallUnsigned,
+        kitsune_p2p::dependencies::url2::url2, AppBundleSource, KitsuneP2pConfig, MembraneProof,
+        SerializedBytes, Signal, TransportConfig, UnsafeBytes, X25519PubKey, XSalsa20Poly1305Data,
+        XSalsa20Poly1305EncryptedData, XSalsa20Poly1305Nonce, ZomeCallUnsigned,
     },
 };
 use holochain_client::{AdminWebsocket, AgentPubKey, AppWebsocket, InstallAppPayload, ZomeCall};
 use holochain_state::nonce::fresh_nonce;
-use holomessage_integrity::HoloMessage;
+use holomessage_integrity::{
+    check_message_expiry, check_message_text, check_poll, Attachment, AttachmentChunk,
+    CreateMessageInput, DirectMessage, DirectMessageSent, EncryptedContent, HoloMessage,
+    HoloMessageProperties, HoloMessageSignal, Invite, MessageContent, MessagesCursor, MessagesPage,
+    MessagesPageInput, Moderation, NotifyTypingInput, Poll, PollResults, Presence, PresenceStatus,
+    ReadMarker, SealedBox, SendDirectMessageInput, UploadFileInput, Vote, ATTACHMENT_CHUNK_SIZE,
+    MAX_ATTACHMENT_SIZE,
+};
 use serde::{de::DeserializeOwned, Serialize};
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     fmt::Debug,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, Mutex},
+    time::Duration,
 };
 
 const CONDUCTOR_CONFIG_FILE: &str = "conductor-config.yaml";
+const ROLE_NAME: &str = "holomessage";
+
+/// Shortest nickname prefix the profiles zome searches for.
+pub const MIN_MEMBER_SEARCH_LENGTH: usize = 3;
+
+/// Profile field holding the agent's x25519 public key, base64 encoded.
+pub const ENCRYPTION_KEY_FIELD: &str = "encryption_key";
+
+/// A message of the shared feed. `text` is `None` for encrypted messages that
+/// weren't sealed for this agent.
+#[derive(Clone, Debug)]
+pub struct FeedMessage {
+    pub action_hash: ActionHash,
+    pub author: AgentPubKey,
+    pub timestamp: Timestamp,
+    pub text: Option<String>,
+    pub encrypted: bool,
+    pub attachment: Option<EntryHash>,
+    pub expires_at: Option<Timestamp>,
+}
+
+impl FeedMessage {
+    pub fn is_expired(&self, now: Timestamp) -> bool {
+        self.expires_at
+            .map_or(false, |expires_at| expires_at <= now)
+    }
+}
+
+/// Feed messages newest first, with the cursor of the next older page if
+/// there is one.
+#[derive(Clone, Debug)]
+pub struct FeedPage {
+    pub messages: Vec<FeedMessage>,
+    pub older: Option<MessagesCursor>,
+}
+
+impl FeedPage {
+    /// The messages of `loaded` older than this page, which covers the rest
+    /// when it is the newest page. Messages are ordered like the cursor, by
+    /// timestamp and then by action hash.
+    pub fn older_loaded(&self, loaded: Vec<FeedMessage>) -> Vec<FeedMessage> {
+        match (&self.older, self.messages.last()) {
+            (Some(_), Some(oldest)) => loaded
+                .into_iter()
+                .filter(|feed_message| {
+                    (feed_message.timestamp, &feed_message.action_hash)
+                        < (oldest.timestamp, &oldest.action_hash)
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// A direct message as stored on this agent's source chain.
+#[derive(Clone, Debug)]
+pub struct DirectMessageItem {
+    pub action_hash: ActionHash,
+    pub timestamp: Timestamp,
+    pub direct_message: DirectMessage,
+    pub text: Option<String>,
+}
+
+/// Change of an agent's presence, `status` is `None` when the agent went
+/// offline.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PresenceChange {
+    pub agent: AgentPubKey,
+    pub status: Option<PresenceStatus>,
+}
 
 #[derive(Clone)]
 pub struct Happ {
@@ -33,6 +124,9 @@ pub struct Happ {
     pub admin_ws: Arc<AdminWebsocket>,
     pub app_ws: Arc<AppWebsocket>,
     pub cell_id: Arc<CellId>,
+    pub search_index: Arc<SearchIndex>,
+    /// Profiles of other agents, dropped when they signal an update.
+    profile_cache: Arc<Mutex<HashMap<AgentPubKey, Profile>>>,
 }
 
 impl Debug for Happ {
@@ -42,7 +136,13 @@ impl Debug for Happ {
 }
 
 impl Happ {
-    pub async fn start_holochain_app(path: PathBuf) -> Result<Happ, String> {
+    /// Start the conductor in `path` and install the app if it isn't yet. An
+    /// invite code is needed to install the app in an invite-only network and
+    /// ignored otherwise.
+    pub async fn start_holochain_app(
+        path: PathBuf,
+        invite_code: Option<String>,
+    ) -> Result<Happ, String> {
         let conductor_config_path = path.join(CONDUCTOR_CONFIG_FILE);
         let conductor_config = if conductor_config_path.exists() {
             println!("conductor exists");
@@ -87,7 +187,7 @@ impl Happ {
                 conductor_config_contents
             );
 
-            std::fs::create_dir_all(path)
+            std::fs::create_dir_all(&path)
                 .map_err(|err| format!("could not create holochain directory: {:?}", err))?;
             std::fs::write(conductor_config_path, conductor_config_contents)
                 .map_err(|err| format!("writing conductor config to file failed: {:?}", err))?;
@@ -95,6 +195,8 @@ impl Happ {
             conductor_config
         };
 
+        let search_index = SearchIndex::open(&path)?;
+
         holochain_trace::test_run().ok();
         let conductor = ConductorBuilder::default()
             .config(conductor_config.clone())
@@ -128,13 +230,17 @@ impl Happ {
                 .map_err(|err| format!("error generating an agent pub key: {:?}", err))?;
             println!("generated agent pub key {:?}", agent_key);
 
+            let mut membrane_proofs = HashMap::new();
+            if let Some(invite_code) = invite_code {
+                membrane_proofs.insert(ROLE_NAME.to_string(), membrane_proof(&invite_code)?);
+            }
             let install_app_payload = InstallAppPayload {
                 source: AppBundleSource::Path(
                     Path::new("happ/workdir/holomessage.happ").to_path_buf(),
                 ),
                 agent_key: agent_key.clone(),
                 installed_app_id: None,
-                membrane_proofs: HashMap::new(),
+                membrane_proofs,
                 network_seed: None,
             };
             let app_info = admin_ws
@@ -168,9 +274,7 @@ impl Happ {
             app.cell_info
         };
 
-        let cells = cell_info
-            .get("holomessage")
-            .ok_or_else(|| "cell not found")?;
+        let cells = cell_info.get(ROLE_NAME).ok_or_else(|| "cell not found")?;
         let cell_id = if let CellInfo::Provisioned(p) = &cells[0] {
             p.cell_id.clone()
         } else {
@@ -203,15 +307,51 @@ impl Happ {
             admin_ws: Arc::new(admin_ws),
             app_ws: Arc::new(app_ws),
             cell_id: Arc::new(cell_id),
+            search_index: Arc::new(search_index),
+            profile_cache: Arc::new(Mutex::new(HashMap::new())),
         };
         Ok(happ)
     }
 
+    /// Check a nickname for a new profile against the profiles zome's length
+    /// rule and the nicknames of other agents.
+    pub async fn check_nickname(&self, nickname: &str) -> Result<Option<NicknameProblem>, String> {
+        if let Some(problem) = check_nickname_length(nickname) {
+            return Ok(Some(problem));
+        }
+        let taken: HashSet<String> = self
+            .search_members(nickname.to_string())
+            .await?
+            .into_values()
+            .map(|profile| profile.nickname.to_lowercase())
+            .collect();
+        if taken.contains(&nickname.trim().to_lowercase()) {
+            return Ok(Some(NicknameProblem::Taken {
+                suggestions: suggest_nicknames(nickname, &taken),
+            }));
+        }
+        Ok(None)
+    }
+
     pub async fn create_profile(&self, nickname: String) -> Result<Profile, String> {
-        let profile = Profile {
-            nickname,
-            fields: BTreeMap::new(),
-        };
+        let nickname = nickname.trim().to_string();
+        if let Some(problem) = self.check_nickname(&nickname).await? {
+            return Err(problem.to_string());
+        }
+        // every profile publishes an encryption key so that others can seal
+        // messages for this agent
+        let encryption_key = self
+            .conductor
+            .keystore()
+            .new_x25519_keypair_random()
+            .await
+            .map_err(|err| format!("error generating an encryption key: {:?}", err))?;
+        let mut fields = BTreeMap::new();
+        fields.insert(
+            ENCRYPTION_KEY_FIELD.to_string(),
+            BASE64.encode(encryption_key.as_ref()),
+        );
+        let profile = Profile { nickname, fields };
         let profile_record: Record = self
             .call_zome(
                 self.cell_id.agent_pubkey().clone(),
@@ -224,6 +364,101 @@ impl Happ {
         Ok(profile)
     }
 
+    /// Change this agent's nickname and profile details. Fields the details
+    /// don't cover, like the encryption key, are kept.
+    pub async fn update_profile(
+        &self,
+        nickname: String,
+        details: ProfileDetails,
+    ) -> Result<Profile, String> {
+        if details.bio.len() > MAX_BIO_LENGTH {
+            return Err(format!("bio exceeds {MAX_BIO_LENGTH} bytes"));
+        }
+        let current_profile = self
+            .fetch_profile(self.cell_id.agent_pubkey().clone())
+            .await?
+            .ok_or("no profile to update".to_string())?;
+        let mut fields = current_profile.fields;
+        details.apply_to(&mut fields);
+        let profile = Profile { nickname, fields };
+        let _profile_record: Record = self
+            .call_zome(
+                self.cell_id.agent_pubkey().clone(),
+                "profiles".into(),
+                "update_profile".into(),
+                profile.clone(),
+            )
+            .await?;
+        self.profile_cache
+            .lock()
+            .unwrap()
+            .remove(self.cell_id.agent_pubkey());
+        if let Err(err) = self.notify_profile_updated().await {
+            eprintln!("could not notify others about the profile update: {err}");
+        }
+        Ok(profile)
+    }
+
+    async fn notify_profile_updated(&self) -> Result<(), String> {
+        let agents: Vec<AgentPubKey> = self
+            .fetch_all_agents()
+            .await?
+            .into_iter()
+            .filter(|agent| agent != self.cell_id.agent_pubkey())
+            .collect();
+        self.call_zome(
+            self.cell_id.agent_pubkey().clone(),
+            "holomessage".into(),
+            "notify_profile_updated".into(),
+            agents,
+        )
+        .await
+    }
+
+    /// Profiles of the given agents, from the cache where possible. Agents
+    /// without a profile are left out.
+    pub async fn fetch_profiles(
+        &self,
+        agents: Vec<AgentPubKey>,
+    ) -> Result<HashMap<AgentPubKey, Profile>, String> {
+        let mut profiles = HashMap::new();
+        let mut missing = Vec::new();
+        {
+            let profile_cache = self.profile_cache.lock().unwrap();
+            for agent in agents {
+                match profile_cache.get(&agent) {
+                    Some(profile) => {
+                        profiles.insert(agent, profile.clone());
+                    }
+                    None if !missing.contains(&agent) => missing.push(agent),
+                    None => (),
+                }
+            }
+        }
+        if missing.is_empty() {
+            return Ok(profiles);
+        }
+
+        let records: Vec<(AgentPubKey, Option<Record>)> = self
+            .call_zome(
+                self.cell_id.agent_pubkey().clone(),
+                "holomessage".into(),
+                "get_agent_profiles".into(),
+                missing,
+            )
+            .await?;
+        let mut profile_cache = self.profile_cache.lock().unwrap();
+        for (agent, record) in records {
+            if let Some(record) = record {
+                let profile = Profile::try_from(record)
+                    .map_err(|err| format!("could not decode profile: {:?}", err))?;
+                profile_cache.insert(agent.clone(), profile.clone());
+                profiles.insert(agent, profile);
+            }
+        }
+        Ok(profiles)
+    }
+
     pub async fn fetch_profile(&self, agent_key: AgentPubKey) -> Result<Option<Profile>, String> {
         let result: Option<Record> = self
             .call_zome(
@@ -243,33 +478,779 @@ impl Happ {
         }
     }
 
-    pub async fn create_message(&self, message: String) -> Result<ActionHash, String> {
+    /// Agents that have created a profile.
+    pub async fn fetch_all_agents(&self) -> Result<Vec<AgentPubKey>, String> {
+        let links: Vec<Link> = self
+            .call_zome(
+                self.cell_id.agent_pubkey().clone(),
+                "profiles".into(),
+                "get_agents_with_profile".into(),
+                (),
+            )
+            .await?;
+        Ok(links
+            .into_iter()
+            .filter_map(|link| link.target.into_agent_pub_key())
+            .collect())
+    }
+
+    /// Profiles of all agents that have created one.
+    pub async fn fetch_members(&self) -> Result<HashMap<AgentPubKey, Profile>, String> {
+        let agents = self.fetch_all_agents().await?;
+        self.fetch_profiles(agents).await
+    }
+
+    /// Profiles whose nickname starts with `nickname_prefix`, ignoring case.
+    /// The profiles zome only searches prefixes of at least
+    /// `MIN_MEMBER_SEARCH_LENGTH` characters, shorter ones filter all members.
+    pub async fn search_members(
+        &self,
+        nickname_prefix: String,
+    ) -> Result<HashMap<AgentPubKey, Profile>, String> {
+        let nickname_prefix = nickname_prefix.trim().to_lowercase();
+        if nickname_prefix.chars().count() < MIN_MEMBER_SEARCH_LENGTH {
+            let mut members = self.fetch_members().await?;
+            members.retain(|_, profile| {
+                profile
+                    .nickname
+                    .to_lowercase()
+                    .starts_with(&nickname_prefix)
+            });
+            return Ok(members);
+        }
+
+        let links: Vec<Link> = self
+            .call_zome(
+                self.cell_id.agent_pubkey().clone(),
+                "profiles".into(),
+                "search_agents".into(),
+                nickname_prefix,
+            )
+            .await?;
+        let agents = links
+            .into_iter()
+            .filter_map(|link| link.target.into_agent_pub_key())
+            .collect();
+        self.fetch_profiles(agents).await
+    }
+
+    /// Create a message in the shared feed. If `encrypt` is set, the text is
+    /// sealed for all agents with a profile.
+    pub async fn create_message(
+        &self,
+        message: String,
+        encrypt: bool,
+        attachment: Option<EntryHash>,
+        expires_at: Option<Timestamp>,
+    ) -> Result<ActionHash, String> {
+        if let Some(reason) = check_message_text(&message) {
+            return Err(reason);
+        }
+        if let Some(reason) =
+            expires_at.and_then(|expires_at| check_message_expiry(expires_at, Timestamp::now()))
+        {
+            return Err(reason);
+        }
+        let content = if encrypt {
+            let recipients = self.fetch_all_agents().await?;
+            self.seal(&message, recipients).await?
+        } else {
+            MessageContent::Text(message)
+        };
         self.call_zome(
             self.cell_id.agent_pubkey().clone(),
             "holomessage".into(),
             "create_message".into(),
-            message,
+            CreateMessageInput {
+                content,
+                attachment,
+                expires_at,
+            },
         )
         .await
     }
 
-    pub async fn fetch_messages(&self) -> Result<Vec<HoloMessage>, String> {
+    /// Whether another agent online gets this agent's feed message from the
+    /// DHT, so peers can see it.
+    pub async fn is_message_seen(&self, action_hash: ActionHash) -> Result<bool, String> {
         self.call_zome(
             self.cell_id.agent_pubkey().clone(),
             "holomessage".into(),
-            "get_messages".into(),
-            (),
+            "is_message_seen".into(),
+            action_hash,
         )
         .await
-        .map(|records: Vec<Record>| {
-            records
-                .into_iter()
-                .map(TryFrom::try_from)
-                .flatten()
-                .collect()
+    }
+
+    /// Up to `limit` feed messages before the cursor `before`, or the newest
+    /// ones without it.
+    pub async fn fetch_messages_page(
+        &self,
+        before: Option<MessagesCursor>,
+        limit: u32,
+    ) -> Result<FeedPage, String> {
+        let page: MessagesPage = self
+            .call_zome(
+                self.cell_id.agent_pubkey().clone(),
+                "holomessage".into(),
+                "get_messages_page".into(),
+                MessagesPageInput { before, limit },
+            )
+            .await?;
+        Ok(FeedPage {
+            messages: self.feed_messages(page.records).await,
+            older: page.older,
         })
     }
 
+    /// Messages that mention this agent, newest first.
+    pub async fn fetch_mentions(&self) -> Result<Vec<FeedMessage>, String> {
+        let records: Vec<Record> = self
+            .call_zome(
+                self.cell_id.agent_pubkey().clone(),
+                "holomessage".into(),
+                "get_my_mentions".into(),
+                (),
+            )
+            .await?;
+        Ok(self.feed_messages(records).await)
+    }
+
+    pub async fn pin_message(&self, message_hash: ActionHash) -> Result<(), String> {
+        self.call_zome(
+            self.cell_id.agent_pubkey().clone(),
+            "holomessage".into(),
+            "pin_message".into(),
+            message_hash,
+        )
+        .await
+    }
+
+    pub async fn unpin_message(&self, message_hash: ActionHash) -> Result<(), String> {
+        self.call_zome(
+            self.cell_id.agent_pubkey().clone(),
+            "holomessage".into(),
+            "unpin_message".into(),
+            message_hash,
+        )
+        .await
+    }
+
+    /// Pinned messages of the feed, newest first.
+    pub async fn fetch_pinned(&self) -> Result<Vec<FeedMessage>, String> {
+        let records: Vec<Record> = self
+            .call_zome(
+                self.cell_id.agent_pubkey().clone(),
+                "holomessage".into(),
+                "get_pinned".into(),
+                (),
+            )
+            .await?;
+        Ok(self.feed_messages(records).await)
+    }
+
+    pub async fn create_poll(
+        &self,
+        question: String,
+        options: Vec<String>,
+        closes_at: Timestamp,
+    ) -> Result<ActionHash, String> {
+        let poll = Poll {
+            question,
+            options,
+            closes_at,
+        };
+        if let Some(reason) = check_poll(&poll, Timestamp::now()) {
+            return Err(reason);
+        }
+        self.call_zome(
+            self.cell_id.agent_pubkey().clone(),
+            "holomessage".into(),
+            "create_poll".into(),
+            poll,
+        )
+        .await
+    }
+
+    pub async fn vote(&self, poll: ActionHash, option: u32) -> Result<ActionHash, String> {
+        self.call_zome(
+            self.cell_id.agent_pubkey().clone(),
+            "holomessage".into(),
+            "vote".into(),
+            Vote { poll, option },
+        )
+        .await
+    }
+
+    /// Republish this agent's feed messages from a cell of an older version of
+    /// the DNA in this conductor. Returns the number of migrated messages.
+    pub async fn migrate_messages_from(&self, old_cell_id: CellId) -> Result<usize, String> {
+        let records: Vec<Record> = self
+            .call_cell_zome(
+                old_cell_id.clone(),
+                old_cell_id.agent_pubkey().clone(),
+                "holomessage".into(),
+                "get_messages".into(),
+                (),
+            )
+            .await?;
+        let inputs = migrated_messages(records, old_cell_id.agent_pubkey(), Timestamp::now());
+        // messages are spread out to stay below the rate limit
+        let pause = match self.fetch_dna_properties().await?.message_rate_limit {
+            Some(rate_limit) => {
+                Duration::from_secs(rate_limit.period_seconds) / rate_limit.max_messages.max(1)
+                    + Duration::from_secs(1)
+            }
+            None => Duration::ZERO,
+        };
+        let count = inputs.len();
+        for (i, input) in inputs.into_iter().enumerate() {
+            if i > 0 {
+                tokio::time::sleep(pause).await;
+            }
+            let _: ActionHash = self
+                .call_zome(
+                    self.cell_id.agent_pubkey().clone(),
+                    "holomessage".into(),
+                    "create_message".into(),
+                    input,
+                )
+                .await?;
+        }
+        Ok(count)
+    }
+
+    /// Polls of the feed with their results, newest first.
+    pub async fn fetch_polls(&self) -> Result<Vec<PollResults>, String> {
+        self.call_zome(
+            self.cell_id.agent_pubkey().clone(),
+            "holomessage".into(),
+            "get_polls".into(),
+            (),
+        )
+        .await
+    }
+
+    pub async fn fetch_dna_properties(&self) -> Result<HoloMessageProperties, String> {
+        self.call_zome(
+            self.cell_id.agent_pubkey().clone(),
+            "holomessage".into(),
+            "get_dna_properties".into(),
+            (),
+        )
+        .await
+    }
+
+    /// Sign an invite to this network, to be passed to the invitee as code.
+    pub async fn create_invite_code(&self) -> Result<String, String> {
+        let invite: Invite = self
+            .call_zome(
+                self.cell_id.agent_pubkey().clone(),
+                "holomessage".into(),
+                "create_invite".into(),
+                (),
+            )
+            .await?;
+        let invite = SerializedBytes::try_from(invite)
+            .map_err(|err| format!("could not serialize invite: {:?}", err))?;
+        Ok(BASE64.encode(invite.bytes()))
+    }
+
+    /// Moderators, hidden messages and blocked agents.
+    pub async fn fetch_moderation(&self) -> Result<Moderation, String> {
+        self.call_zome(
+            self.cell_id.agent_pubkey().clone(),
+            "holomessage".into(),
+            "get_moderation".into(),
+            (),
+        )
+        .await
+    }
+
+    /// Make an agent a moderator. Only the progenitor may do this.
+    pub async fn grant_moderator(&self, agent: AgentPubKey) -> Result<ActionHash, String> {
+        self.call_zome(
+            self.cell_id.agent_pubkey().clone(),
+            "holomessage".into(),
+            "grant_moderator".into(),
+            agent,
+        )
+        .await
+    }
+
+    pub async fn hide_message(&self, message_hash: ActionHash) -> Result<(), String> {
+        self.call_zome(
+            self.cell_id.agent_pubkey().clone(),
+            "holomessage".into(),
+            "hide_message".into(),
+            message_hash,
+        )
+        .await
+    }
+
+    pub async fn unhide_message(&self, message_hash: ActionHash) -> Result<(), String> {
+        self.call_zome(
+            self.cell_id.agent_pubkey().clone(),
+            "holomessage".into(),
+            "unhide_message".into(),
+            message_hash,
+        )
+        .await
+    }
+
+    pub async fn block_agent(&self, agent: AgentPubKey) -> Result<(), String> {
+        self.call_zome(
+            self.cell_id.agent_pubkey().clone(),
+            "holomessage".into(),
+            "block_agent".into(),
+            agent,
+        )
+        .await
+    }
+
+    pub async fn unblock_agent(&self, agent: AgentPubKey) -> Result<(), String> {
+        self.call_zome(
+            self.cell_id.agent_pubkey().clone(),
+            "holomessage".into(),
+            "unblock_agent".into(),
+            agent,
+        )
+        .await
+    }
+
+    /// Decode and decrypt message records.
+    async fn feed_messages(&self, records: Vec<Record>) -> Vec<FeedMessage> {
+        let holo_messages: Vec<(Record, HoloMessage)> = records
+            .into_iter()
+            .filter_map(|record| {
+                let holo_message = HoloMessage::try_from(&record).ok()?;
+                Some((record, holo_message))
+            })
+            .collect();
+        let encryption_key = if holo_messages.iter().any(|(_, m)| m.content.is_encrypted()) {
+            self.encryption_key().await.ok()
+        } else {
+            None
+        };
+        let mut messages = Vec::with_capacity(holo_messages.len());
+        for (record, holo_message) in holo_messages {
+            messages.push(FeedMessage {
+                action_hash: record.action_address().clone(),
+                author: record.action().author().clone(),
+                timestamp: record.action().timestamp(),
+                encrypted: holo_message.content.is_encrypted(),
+                text: self
+                    .open(&holo_message.content, encryption_key.as_ref())
+                    .await,
+                attachment: holo_message.attachment,
+                expires_at: holo_message.expires_at,
+            });
+        }
+        self.index_messages(
+            messages
+                .iter()
+                .filter_map(|message| {
+                    Some(IndexedMessage {
+                        action_hash: message.action_hash.clone(),
+                        author: message.author.clone(),
+                        timestamp: message.timestamp,
+                        text: message.text.clone()?,
+                        peer: None,
+                    })
+                })
+                .collect(),
+        )
+        .await;
+        messages
+    }
+
+    pub async fn send_direct_message(
+        &self,
+        to: AgentPubKey,
+        text: String,
+        encrypt: bool,
+    ) -> Result<DirectMessageSent, String> {
+        if let Some(reason) = check_message_text(&text) {
+            return Err(reason);
+        }
+        let content = if encrypt {
+            self.seal(&text, vec![to.clone()]).await?
+        } else {
+            MessageContent::Text(text)
+        };
+        self.call_zome(
+            self.cell_id.agent_pubkey().clone(),
+            "holomessage".into(),
+            "send_dm".into(),
+            SendDirectMessageInput { to, content },
+        )
+        .await
+    }
+
+    pub async fn fetch_direct_messages(&self) -> Result<Vec<DirectMessageItem>, String> {
+        let records: Vec<Record> = self
+            .call_zome(
+                self.cell_id.agent_pubkey().clone(),
+                "holomessage".into(),
+                "get_direct_messages".into(),
+                (),
+            )
+            .await?;
+        let encryption_key = self.encryption_key().await.ok();
+        let mut direct_messages = Vec::new();
+        for record in records {
+            let action_hash = record.action_address().clone();
+            let timestamp = record.action().timestamp();
+            if let Ok(direct_message) = DirectMessage::try_from(record) {
+                direct_messages.push(DirectMessageItem {
+                    action_hash,
+                    timestamp,
+                    text: self
+                        .open(&direct_message.content, encryption_key.as_ref())
+                        .await,
+                    direct_message,
+                });
+            }
+        }
+        self.index_direct_messages(&direct_messages).await;
+        Ok(direct_messages)
+    }
+
+    /// Search all messages this agent has read so far, feed messages and
+    /// direct messages alike.
+    pub async fn search_messages(&self, query: SearchQuery) -> Result<Vec<SearchHit>, String> {
+        let search_index = self.search_index.clone();
+        tokio::task::spawn_blocking(move || search_index.search(&query))
+            .await
+            .map_err(|err| format!("search task failed: {:?}", err))?
+    }
+
+    async fn index_direct_messages(&self, direct_messages: &[DirectMessageItem]) {
+        let my_agent_key = self.cell_id.agent_pubkey();
+        self.index_messages(
+            direct_messages
+                .iter()
+                .filter_map(|item| {
+                    Some(IndexedMessage {
+                        action_hash: item.action_hash.clone(),
+                        author: item.direct_message.from.clone(),
+                        timestamp: item.timestamp,
+                        text: item.text.clone()?,
+                        peer: Some(item.direct_message.peer(my_agent_key).clone()),
+                    })
+                })
+                .collect(),
+        )
+        .await;
+    }
+
+    /// Add messages to the search index. Indexing errors are logged only, the
+    /// messages are still displayed.
+    async fn index_messages(&self, messages: Vec<IndexedMessage>) {
+        let search_index = self.search_index.clone();
+        match tokio::task::spawn_blocking(move || search_index.add_messages(messages)).await {
+            Ok(Ok(())) => (),
+            Ok(Err(err)) => eprintln!("error indexing messages: {err}"),
+            Err(err) => eprintln!("indexing task failed: {:?}", err),
+        }
+    }
+
+    /// Upload a file chunk by chunk and create an attachment for it.
+    pub async fn upload_file(&self, path: &Path) -> Result<EntryHash, String> {
+        let content = tokio::fs::read(path)
+            .await
+            .map_err(|err| format!("could not read file: {:?}", err))?;
+        if content.is_empty() || content.len() as u64 > MAX_ATTACHMENT_SIZE {
+            return Err(format!(
+                "attachments must be between 1 and {MAX_ATTACHMENT_SIZE} bytes"
+            ));
+        }
+        let filename = path
+            .file_name()
+            .map(|filename| filename.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mime_type = mime_guess::from_path(path)
+            .first_or_octet_stream()
+            .to_string();
+
+        let mut chunks = Vec::new();
+        for data in content.chunks(ATTACHMENT_CHUNK_SIZE) {
+            let chunk_hash: EntryHash = self
+                .call_zome(
+                    self.cell_id.agent_pubkey().clone(),
+                    "holomessage".into(),
+                    "upload_chunk".into(),
+                    AttachmentChunk {
+                        data: data.to_vec(),
+                    },
+                )
+                .await?;
+            chunks.push(chunk_hash);
+        }
+        self.call_zome(
+            self.cell_id.agent_pubkey().clone(),
+            "holomessage".into(),
+            "upload_file".into(),
+            UploadFileInput {
+                filename,
+                mime_type,
+                chunks,
+            },
+        )
+        .await
+    }
+
+    /// Metadata of an attachment.
+    pub async fn fetch_attachment(&self, attachment_hash: EntryHash) -> Result<Attachment, String> {
+        let attachment: Option<Attachment> = self
+            .call_zome(
+                self.cell_id.agent_pubkey().clone(),
+                "holomessage".into(),
+                "download_file".into(),
+                attachment_hash,
+            )
+            .await?;
+        attachment.ok_or_else(|| "attachment not found".to_string())
+    }
+
+    /// Download the content of an attachment chunk by chunk.
+    pub async fn download_file(&self, attachment: &Attachment) -> Result<Vec<u8>, String> {
+        let mut content = Vec::with_capacity(attachment.size as usize);
+        for chunk_hash in attachment.chunks.iter() {
+            let chunk: Option<AttachmentChunk> = self
+                .call_zome(
+                    self.cell_id.agent_pubkey().clone(),
+                    "holomessage".into(),
+                    "download_chunk".into(),
+                    chunk_hash.clone(),
+                )
+                .await?;
+            let chunk = chunk.ok_or_else(|| format!("chunk {chunk_hash:?} not found"))?;
+            content.extend(chunk.data);
+        }
+        Ok(content)
+    }
+
+    pub async fn fetch_read_markers(&self) -> Result<Vec<ReadMarker>, String> {
+        self.call_zome(
+            self.cell_id.agent_pubkey().clone(),
+            "holomessage".into(),
+            "get_read_markers".into(),
+            (),
+        )
+        .await
+    }
+
+    pub async fn set_read_marker(&self, read_marker: ReadMarker) -> Result<ActionHash, String> {
+        self.call_zome(
+            self.cell_id.agent_pubkey().clone(),
+            "holomessage".into(),
+            "set_read_marker".into(),
+            read_marker,
+        )
+        .await
+    }
+
+    /// Heartbeat telling other agents that this agent is online or away.
+    pub async fn announce_presence(&self, status: PresenceStatus) -> Result<(), String> {
+        self.call_zome(
+            self.cell_id.agent_pubkey().clone(),
+            "holomessage".into(),
+            "announce_presence".into(),
+            status,
+        )
+        .await
+    }
+
+    pub async fn fetch_online_agents(&self) -> Result<Vec<Presence>, String> {
+        self.call_zome(
+            self.cell_id.agent_pubkey().clone(),
+            "holomessage".into(),
+            "get_online_agents".into(),
+            (),
+        )
+        .await
+    }
+
+    /// Presence changes of all agents, polled every `poll_interval`. The
+    /// first poll reports every agent that is online or away.
+    pub fn presence_changes(
+        &self,
+        poll_interval: Duration,
+    ) -> impl Stream<Item = PresenceChange> + Send + 'static {
+        futures::stream::unfold(
+            (self.clone(), None),
+            move |(happ, known): (Happ, Option<HashMap<AgentPubKey, PresenceStatus>>)| async move {
+                if known.is_some() {
+                    tokio::time::sleep(poll_interval).await;
+                }
+                let mut known = known.unwrap_or_default();
+                let changes = match happ.fetch_online_agents().await {
+                    Ok(online) => diff_presence(&mut known, online),
+                    Err(err) => {
+                        eprintln!("could not fetch online agents: {err}");
+                        Vec::new()
+                    }
+                };
+                Some((changes, (happ, Some(known))))
+            },
+        )
+        .flat_map(futures::stream::iter)
+    }
+
+    /// Let `agents` know that this agent is typing.
+    pub async fn notify_typing(
+        &self,
+        agents: Vec<AgentPubKey>,
+        nickname: String,
+    ) -> Result<(), String> {
+        self.call_zome(
+            self.cell_id.agent_pubkey().clone(),
+            "holomessage".into(),
+            "notify_typing".into(),
+            NotifyTypingInput { agents, nickname },
+        )
+        .await
+    }
+
+    /// Signals emitted by the holomessage zome of this agent's cell. Received
+    /// direct messages are added to the search index and updated profiles are
+    /// dropped from the profile cache on the way.
+    pub fn holomessage_signals(&self) -> impl Stream<Item = HoloMessageSignal> + Send + 'static {
+        let cell_id = (*self.cell_id).clone();
+        let happ = self.clone();
+        self.conductor
+            .signal_broadcaster()
+            .subscribe_merged()
+            .filter_map(move |signal| {
+                let holomessage_signal = match signal {
+                    Signal::App {
+                        cell_id: signal_cell_id,
+                        zome_name,
+                        signal,
+                    } if signal_cell_id == cell_id && zome_name == "holomessage".into() => signal
+                        .into_inner()
+                        .decode::<HoloMessageSignal>()
+                        .map_err(|err| eprintln!("could not decode signal: {:?}", err))
+                        .ok(),
+                    _ => None,
+                };
+                let happ = happ.clone();
+                async move {
+                    if let Some(HoloMessageSignal::ProfileUpdated { agent }) = &holomessage_signal {
+                        happ.profile_cache.lock().unwrap().remove(agent);
+                    }
+                    if let Some(HoloMessageSignal::DirectMessageReceived {
+                        action_hash,
+                        timestamp,
+                        direct_message,
+                    }) = &holomessage_signal
+                    {
+                        let encryption_key = happ.encryption_key().await.ok();
+                        let item = DirectMessageItem {
+                            action_hash: action_hash.clone(),
+                            timestamp: *timestamp,
+                            text: happ
+                                .open(&direct_message.content, encryption_key.as_ref())
+                                .await,
+                            direct_message: direct_message.clone(),
+                        };
+                        happ.index_direct_messages(&[item]).await;
+                    }
+                    holomessage_signal
+                }
+            })
+    }
+
+    /// This agent's x25519 public key as published in its profile.
+    async fn encryption_key(&self) -> Result<[u8; 32], String> {
+        let profile = self
+            .fetch_profile(self.cell_id.agent_pubkey().clone())
+            .await?
+            .ok_or("no profile found")?;
+        encryption_key_of(&profile).ok_or_else(|| "profile has no encryption key".to_string())
+    }
+
+    /// Seal a text for the given agents and this agent itself. Agents without
+    /// an encryption key in their profile are skipped.
+    async fn seal(
+        &self,
+        text: &str,
+        recipients: Vec<AgentPubKey>,
+    ) -> Result<MessageContent, String> {
+        let sender_key = self.encryption_key().await?;
+        let mut recipient_keys = vec![sender_key];
+        for agent in recipients {
+            match self
+                .fetch_profile(agent.clone())
+                .await?
+                .as_ref()
+                .and_then(encryption_key_of)
+            {
+                Some(key) if !recipient_keys.contains(&key) => recipient_keys.push(key),
+                Some(_) => (),
+                None => println!("agent {agent:?} has no encryption key, skipping"),
+            }
+        }
+
+        let mut boxes = Vec::with_capacity(recipient_keys.len());
+        for recipient_key in recipient_keys {
+            let encrypted = self
+                .conductor
+                .keystore()
+                .crypto_box_xsalsa(
+                    X25519PubKey::from(sender_key),
+                    X25519PubKey::from(recipient_key),
+                    XSalsa20Poly1305Data::from(text.as_bytes().to_vec()),
+                )
+                .await
+                .map_err(|err| format!("error encrypting message: {:?}", err))?;
+            let nonce = <[u8; 24]>::try_from(encrypted.as_nonce_ref().as_ref())
+                .map_err(|err| format!("invalid nonce: {:?}", err))?;
+            boxes.push(SealedBox {
+                recipient_key,
+                nonce,
+                cipher: encrypted.as_encrypted_data_ref().to_vec(),
+            });
+        }
+        Ok(MessageContent::Encrypted(EncryptedContent {
+            sender_key,
+            boxes,
+        }))
+    }
+
+    /// Readable text of a message content, `None` if it was sealed for others.
+    async fn open(
+        &self,
+        content: &MessageContent,
+        recipient_key: Option<&[u8; 32]>,
+    ) -> Option<String> {
+        let encrypted_content = match content {
+            MessageContent::Text(text) => return Some(text.clone()),
+            MessageContent::Encrypted(encrypted_content) => encrypted_content,
+        };
+        let recipient_key = *recipient_key?;
+        let sealed_box = encrypted_content
+            .boxes
+            .iter()
+            .find(|sealed_box| sealed_box.recipient_key == recipient_key)?;
+        let data = self
+            .conductor
+            .keystore()
+            .crypto_box_xsalsa_open(
+                X25519PubKey::from(encrypted_content.sender_key),
+                X25519PubKey::from(recipient_key),
+                XSalsa20Poly1305EncryptedData::new(
+                    XSalsa20Poly1305Nonce::from(sealed_box.nonce),
+                    sealed_box.cipher.clone(),
+                ),
+            )
+            .await
+            .map_err(|err| eprintln!("error decrypting message: {:?}", err))
+            .ok()?;
+        String::from_utf8(data.as_ref().to_vec()).ok()
+    }
+
     async fn call_zome<T, P>(
         &self,
         provenance: AgentPubKey,
@@ -277,6 +1258,28 @@ impl Happ {
         fn_name: FunctionName,
         payload: P,
     ) -> Result<T, String>
+    where
+        T: std::fmt::Debug + DeserializeOwned,
+        P: Serialize + std::fmt::Debug,
+    {
+        self.call_cell_zome(
+            (*self.cell_id).clone(),
+            provenance,
+            zome_name,
+            fn_name,
+            payload,
+        )
+        .await
+    }
+
+    async fn call_cell_zome<T, P>(
+        &self,
+        cell_id: CellId,
+        provenance: AgentPubKey,
+        zome_name: ZomeName,
+        fn_name: FunctionName,
+        payload: P,
+    ) -> Result<T, String>
     where
         T: std::fmt::Debug + DeserializeOwned,
         P: Serialize + std::fmt::Debug,
@@ -286,7 +1289,7 @@ impl Happ {
         let payload = ExternIO::encode(payload)
             .map_err(|err| format!("error encoding payload: {:?}", err))?;
         let profile_zome_call_unsigned = ZomeCallUnsigned {
-            cell_id: (*self.cell_id).clone(),
+            cell_id,
             zome_name,
             fn_name,
             payload,
@@ -311,6 +1314,58 @@ impl Happ {
     }
 }
 
+fn membrane_proof(invite_code: &str) -> Result<MembraneProof, String> {
+    let invite = BASE64
+        .decode(invite_code.trim())
+        .map_err(|err| format!("invalid invite code: {:?}", err))?;
+    let invite = SerializedBytes::from(UnsafeBytes::from(invite));
+    // check the code before installing, the conductor only reports a failed
+    // genesis
+    Invite::try_from(invite.clone()).map_err(|err| format!("invalid invite code: {:?}", err))?;
+    Ok(Arc::new(invite))
+}
+
+/// Update the known presences to the agents online now and return what
+/// changed.
+fn diff_presence(
+    known: &mut HashMap<AgentPubKey, PresenceStatus>,
+    online: Vec<Presence>,
+) -> Vec<PresenceChange> {
+    let mut changes: Vec<PresenceChange> = known
+        .keys()
+        .filter(|agent| !online.iter().any(|presence| &presence.agent == *agent))
+        .map(|agent| PresenceChange {
+            agent: agent.clone(),
+            status: None,
+        })
+        .collect();
+    for change in changes.iter() {
+        known.remove(&change.agent);
+    }
+    for presence in online {
+        if known.get(&presence.agent) != Some(&presence.status) {
+            known.insert(presence.agent.clone(), presence.status);
+            changes.push(PresenceChange {
+                agent: presence.agent,
+                status: Some(presence.status),
+            });
+        }
+    }
+    changes
+}
+
+/// Read marker channel of the direct message conversation with `peer`.
+pub fn direct_message_channel(peer: &AgentPubKey) -> String {
+    format!("dm:{}", AgentPubKeyB64::from(peer.clone()))
+}
+
+fn encryption_key_of(profile: &Profile) -> Option<[u8; 32]> {
+    let key = BASE64
+        .decode(profile.fields.get(ENCRYPTION_KEY_FIELD)?)
+        .ok()?;
+    <[u8; 32]>::try_from(key).ok()
+}
+
 fn vec_to_locked(mut pass_tmp: Vec<u8>) -> std::io::Result<sodoken::BufRead> {
     match sodoken::BufWrite::new_mem_locked(pass_tmp.len()) {
         Err(e) => {
@@ -330,19 +1385,115 @@ fn vec_to_locked(mut pass_tmp: Vec<u8>) -> std::io::Result<sodoken::BufRead> {
 
 #[cfg(test)]
 mod tests {
-    use crate::happ::Happ;
-    use std::env::temp_dir;
+    use crate::happ::{diff_presence, FeedMessage, FeedPage, Happ, PresenceChange};
+    use hdk::prelude::{ActionHash, AgentPubKey, Timestamp};
+    use holomessage_integrity::{MessagesCursor, Presence, PresenceStatus};
+    use std::{collections::HashMap, env::temp_dir};
 
     #[tokio::test(flavor = "multi_thread")]
     async fn holochain_start() {
         let mut path = temp_dir().to_path_buf();
         path.push("some_dir");
         println!("path {:?}", path);
-        let happ = Happ::start_holochain_app(path).await.unwrap();
+        let happ = Happ::start_holochain_app(path, None).await.unwrap();
         (*happ.app_ws)
             .clone()
             .app_info("some_id".to_string())
             .await
             .unwrap();
     }
+
+    #[test]
+    fn presence_diff() {
+        let alice = AgentPubKey::from_raw_36(vec![1; 36]);
+        let bob = AgentPubKey::from_raw_36(vec![2; 36]);
+        let presence = |agent: &AgentPubKey, status| Presence {
+            agent: agent.clone(),
+            status,
+            last_seen: Timestamp::from_micros(0),
+        };
+        let mut known = HashMap::new();
+
+        let changes = diff_presence(&mut known, vec![presence(&alice, PresenceStatus::Online)]);
+        assert_eq!(
+            changes,
+            vec![PresenceChange {
+                agent: alice.clone(),
+                status: Some(PresenceStatus::Online)
+            }]
+        );
+
+        // unchanged agents aren't reported again
+        let changes = diff_presence(
+            &mut known,
+            vec![
+                presence(&alice, PresenceStatus::Online),
+                presence(&bob, PresenceStatus::Away),
+            ],
+        );
+        assert_eq!(
+            changes,
+            vec![PresenceChange {
+                agent: bob.clone(),
+                status: Some(PresenceStatus::Away)
+            }]
+        );
+
+        let changes = diff_presence(&mut known, vec![presence(&bob, PresenceStatus::Away)]);
+        assert_eq!(
+            changes,
+            vec![PresenceChange {
+                agent: alice,
+                status: None
+            }]
+        );
+        assert_eq!(known.len(), 1);
+    }
+
+    #[test]
+    fn older_loaded_messages() {
+        let feed_message = |timestamp, hash_byte| FeedMessage {
+            action_hash: ActionHash::from_raw_36(vec![hash_byte; 36]),
+            author: AgentPubKey::from_raw_36(vec![1; 36]),
+            timestamp: Timestamp::from_micros(timestamp),
+            text: None,
+            encrypted: false,
+            attachment: None,
+            expires_at: None,
+        };
+        let page = FeedPage {
+            messages: vec![feed_message(3, 1), feed_message(2, 5)],
+            older: Some(MessagesCursor {
+                timestamp: Timestamp::from_micros(2),
+                action_hash: ActionHash::from_raw_36(vec![5; 36]),
+            }),
+        };
+        // messages at the time of the page's oldest are older when their
+        // hash is lower
+        let loaded = vec![
+            feed_message(3, 1),
+            feed_message(2, 5),
+            feed_message(2, 4),
+            feed_message(1, 9),
+        ];
+        let older: Vec<_> = page
+            .older_loaded(loaded.clone())
+            .into_iter()
+            .map(|feed_message| feed_message.action_hash)
+            .collect();
+        assert_eq!(
+            older,
+            vec![
+                ActionHash::from_raw_36(vec![4; 36]),
+                ActionHash::from_raw_36(vec![9; 36]),
+            ]
+        );
+
+        // the only page covers the whole feed
+        let page = FeedPage {
+            older: None,
+            ..page
+        };
+        assert!(page.older_loaded(loaded).is_empty());
+    }
 }
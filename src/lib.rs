@@ -1 +1,8 @@
 pub mod happ;
+pub mod markdown;
+pub mod migration;
+pub mod notification;
+pub mod outbox;
+pub mod profile;
+pub mod search;
+pub mod settings;
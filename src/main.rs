@@ -1,25 +1,121 @@
-use std::{path::PathBuf, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use hc_zome_profiles_integrity::Profile;
-use hdk::prelude::ActionHash;
+use hdk::prelude::{ActionHash, AgentPubKey, AgentPubKeyB64, EntryHash, Timestamp};
 use holochain::start_happ;
-use holomessage_integrity::HoloMessage;
+use holomessage_integrity::{
+    mentioned_nicknames, Attachment, DirectMessageSent, HoloMessageProperties, HoloMessageSignal,
+    MessagesCursor, Moderation, PinPermission, PollResults, PresenceStatus, ReadMarker,
+    FEED_CHANNEL, MAX_POLL_OPTIONS,
+};
 use iced::{
-    futures::SinkExt,
-    widget::{column, row, text, text_input, Space, TextInput},
-    Application, Color, Command, Length, Settings, Subscription, Theme,
+    futures::{SinkExt, StreamExt},
+    theme::Palette,
+    widget::{
+        button, checkbox, column, container, horizontal_rule, image, pick_list, progress_bar, row,
+        scrollable, slider, text, text_input, Column, Row, Space, TextInput,
+    },
+    Application, Color, Command, Element, Length, Settings, Subscription, Theme,
+};
+
+use iced_holochain::{
+    happ::{
+        direct_message_channel, DirectMessageItem, FeedMessage, FeedPage, Happ, PresenceChange,
+    },
+    markdown,
+    notification::{Notifications, Severity},
+    outbox::{DeliveryStatus, Outbox, OutgoingMessage},
+    profile::{
+        check_nickname_length, downscale_avatar, format_color, parse_color, NicknameProblem,
+        ProfileDetails,
+    },
+    search::{SearchHit, SearchQuery},
+    settings::{
+        CustomPalette, ThemeSetting, TimestampFormat, UserSettings, MAX_FONT_SCALE, MIN_FONT_SCALE,
+    },
 };
-use iced_holochain::happ::Happ;
 use once_cell::sync::Lazy;
 
-use crate::holochain::{create_message, create_profile, fetch_messages, fetch_profile};
+use crate::holochain::{
+    announce_presence, block_agent, check_nickname, create_invite_code, create_message,
+    create_poll, create_profile, fetch_attachment, fetch_direct_messages, fetch_dna_properties,
+    fetch_mentions, fetch_messages_page, fetch_moderation, fetch_pinned, fetch_polls,
+    fetch_profile, fetch_profiles, fetch_read_markers, grant_moderator, hide_message,
+    is_message_seen, notify_typing, pin_message, save_attachment, search_members, search_messages,
+    send_direct_message, set_read_marker, unblock_agent, unhide_message, unpin_message,
+    update_profile, upload_file, vote,
+};
 
 static PROFILE_INPUT_ID: Lazy<text_input::Id> = Lazy::new(text_input::Id::unique);
 static MESSAGE_INPUT_ID: Lazy<text_input::Id> = Lazy::new(text_input::Id::unique);
+static DIRECT_MESSAGE_INPUT_ID: Lazy<text_input::Id> = Lazy::new(text_input::Id::unique);
+static SEARCH_INPUT_ID: Lazy<text_input::Id> = Lazy::new(text_input::Id::unique);
+static MEMBER_SEARCH_INPUT_ID: Lazy<text_input::Id> = Lazy::new(text_input::Id::unique);
+static FEED_SCROLLABLE_ID: Lazy<scrollable::Id> = Lazy::new(scrollable::Id::unique);
+
+const LOCK_ICON: &str = "🔒";
+const ATTACHMENT_ICON: &str = "📎";
+const SENT_ICON: &str = "✓";
+const SEEN_ICON: &str = "✓✓";
+const PIN_ICON: &str = "📌";
+const EXPIRY_ICON: &str = "⏳";
+const POLL_ICON: &str = "📊";
+/// Edge length of author avatars in the message list.
+const AUTHOR_AVATAR_SIZE: f32 = 24.0;
+/// Consecutive messages of an author within this time are shown as a group.
+const MESSAGE_GROUP_WINDOW: Duration = Duration::from_secs(5 * 60);
+/// Edge length of the avatar in the header.
+const HEADER_AVATAR_SIZE: f32 = 32.0;
+/// Edge length of avatars in the member directory.
+const MEMBER_AVATAR_SIZE: f32 = 48.0;
+/// Edge length of the avatar preview on the profile screen.
+const PROFILE_AVATAR_SIZE: f32 = 96.0;
+/// Width of the result bars of a poll.
+const POLL_BAR_WIDTH: f32 = 200.0;
+/// Messages are wrapped in bubbles of at most this width.
+const BUBBLE_MAX_WIDTH: f32 = 600.0;
+/// Width inline images are scaled to in the message list.
+const INLINE_IMAGE_WIDTH: f32 = 300.0;
+/// Minimum time between two typing notifications sent to other agents.
+const TYPING_NOTIFICATION_INTERVAL: Duration = Duration::from_secs(3);
+/// How long a typing indicator is shown after the last notification.
+const TYPING_INDICATOR_TIMEOUT: Duration = Duration::from_secs(5);
+/// Number of recent messages whose authors are notified about typing.
+const RECENT_PARTICIPANTS_WINDOW: usize = 20;
+/// Interval of the heartbeats announcing this agent's presence. It needs to be
+/// well below the presence timeout of the DNA. Every heartbeat adds two
+/// actions to the agent's chain, so they are far apart.
+const PRESENCE_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// Interval of checking whether this agent's status changed, which is
+/// announced without waiting for the next heartbeat.
+const PRESENCE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Interval of polling the presence of other agents.
+const PRESENCE_POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// Interval of looking for sent messages due for a delivery check, see
+/// `Outbox::delivery_checks_due`.
+const DELIVERY_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Without keyboard or mouse input for this long, the agent is away.
+const AWAY_AFTER: Duration = Duration::from_secs(5 * 60);
+/// Messages fetched per page of the feed.
+const FEED_PAGE_SIZE: u32 = 50;
+/// Feed rows are laid out this far above and below the visible part.
+const FEED_OVERSCAN: f32 = 500.0;
+/// Older messages are loaded when the feed is scrolled this close to the top.
+const LOAD_OLDER_THRESHOLD: f32 = 100.0;
+/// Height of iced's default window, until the window is resized.
+const DEFAULT_WINDOW_HEIGHT: f32 = 768.0;
+/// Date format of the search filters.
+const SEARCH_DATE_FORMAT: &str = "%Y-%m-%d";
 
 #[derive(Clone)]
 enum Flags {
-    HolochainDir(PathBuf),
+    /// Conductor directory and an optional invite code to join with.
+    HolochainDir(PathBuf, Option<String>),
 }
 
 impl Default for Flags {
@@ -34,7 +130,7 @@ impl Default for Flags {
             }
         }
         println!("conductor dir is {:?}", holochain_dir);
-        Flags::HolochainDir(holochain_dir)
+        Flags::HolochainDir(holochain_dir, args.get(2).cloned())
     }
 }
 
@@ -44,36 +140,869 @@ fn main() -> iced::Result {
 }
 
 enum Holomess {
-    Starting,
+    /// Conductor directory and the settings stored in it.
+    Starting(PathBuf, UserSettings),
     Running(State),
     Failed(String),
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Screen {
+    Feed,
+    DirectMessages,
+    Mentions,
+    Search,
+    Members,
+    Profile,
+    Settings,
+}
+
+/// Themes selectable on the settings screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ThemeChoice {
+    Light,
+    Dark,
+    Custom,
+}
+
+impl ThemeChoice {
+    const ALL: [ThemeChoice; 3] = [ThemeChoice::Light, ThemeChoice::Dark, ThemeChoice::Custom];
+
+    fn of(theme: &ThemeSetting) -> Self {
+        match theme {
+            ThemeSetting::Light => ThemeChoice::Light,
+            ThemeSetting::Dark => ThemeChoice::Dark,
+            ThemeSetting::Custom(_) => ThemeChoice::Custom,
+        }
+    }
+}
+
+impl std::fmt::Display for ThemeChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            ThemeChoice::Light => "Light",
+            ThemeChoice::Dark => "Dark",
+            ThemeChoice::Custom => "Custom",
+        };
+        write!(f, "{label}")
+    }
+}
+
+/// Colors of a custom theme.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PaletteColor {
+    Background,
+    Text,
+    Primary,
+    Success,
+    Danger,
+}
+
+impl PaletteColor {
+    const ALL: [PaletteColor; 5] = [
+        PaletteColor::Background,
+        PaletteColor::Text,
+        PaletteColor::Primary,
+        PaletteColor::Success,
+        PaletteColor::Danger,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            PaletteColor::Background => "Background",
+            PaletteColor::Text => "Text",
+            PaletteColor::Primary => "Primary",
+            PaletteColor::Success => "Success",
+            PaletteColor::Danger => "Danger",
+        }
+    }
+
+    fn of<'a>(&self, palette: &'a CustomPalette) -> &'a str {
+        match self {
+            PaletteColor::Background => &palette.background,
+            PaletteColor::Text => &palette.text,
+            PaletteColor::Primary => &palette.primary,
+            PaletteColor::Success => &palette.success,
+            PaletteColor::Danger => &palette.danger,
+        }
+    }
+
+    fn of_mut<'a>(&self, palette: &'a mut CustomPalette) -> &'a mut String {
+        match self {
+            PaletteColor::Background => &mut palette.background,
+            PaletteColor::Text => &mut palette.text,
+            PaletteColor::Primary => &mut palette.primary,
+            PaletteColor::Success => &mut palette.success,
+            PaletteColor::Danger => &mut palette.danger,
+        }
+    }
+}
+
+/// Lifetimes selectable for a new message.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum MessageExpiry {
+    #[default]
+    Never,
+    FiveMinutes,
+    OneHour,
+    OneDay,
+    OneWeek,
+}
+
+impl MessageExpiry {
+    const ALL: [MessageExpiry; 5] = [
+        MessageExpiry::Never,
+        MessageExpiry::FiveMinutes,
+        MessageExpiry::OneHour,
+        MessageExpiry::OneDay,
+        MessageExpiry::OneWeek,
+    ];
+
+    fn lifetime(&self) -> Option<Duration> {
+        match self {
+            MessageExpiry::Never => None,
+            MessageExpiry::FiveMinutes => Some(Duration::from_secs(5 * 60)),
+            MessageExpiry::OneHour => Some(Duration::from_secs(60 * 60)),
+            MessageExpiry::OneDay => Some(Duration::from_secs(24 * 60 * 60)),
+            MessageExpiry::OneWeek => Some(Duration::from_secs(7 * 24 * 60 * 60)),
+        }
+    }
+
+    fn expires_at(&self) -> Option<Timestamp> {
+        self.lifetime()
+            .and_then(|lifetime| (Timestamp::now() + lifetime).ok())
+    }
+}
+
+impl std::fmt::Display for MessageExpiry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            MessageExpiry::Never => "Keep",
+            MessageExpiry::FiveMinutes => "Delete after 5 minutes",
+            MessageExpiry::OneHour => "Delete after 1 hour",
+            MessageExpiry::OneDay => "Delete after 1 day",
+            MessageExpiry::OneWeek => "Delete after 1 week",
+        };
+        write!(f, "{label}")
+    }
+}
+
+/// How long a new poll accepts votes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum PollDuration {
+    OneHour,
+    #[default]
+    OneDay,
+    OneWeek,
+}
+
+impl PollDuration {
+    const ALL: [PollDuration; 3] = [
+        PollDuration::OneHour,
+        PollDuration::OneDay,
+        PollDuration::OneWeek,
+    ];
+
+    fn closes_at(&self) -> Option<Timestamp> {
+        let duration = match self {
+            PollDuration::OneHour => Duration::from_secs(60 * 60),
+            PollDuration::OneDay => Duration::from_secs(24 * 60 * 60),
+            PollDuration::OneWeek => Duration::from_secs(7 * 24 * 60 * 60),
+        };
+        (Timestamp::now() + duration).ok()
+    }
+}
+
+impl std::fmt::Display for PollDuration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            PollDuration::OneHour => "Open for 1 hour",
+            PollDuration::OneDay => "Open for 1 day",
+            PollDuration::OneWeek => "Open for 1 week",
+        };
+        write!(f, "{label}")
+    }
+}
+
+/// Profile of a message author, with avatar and color ready to render.
+struct AuthorView {
+    profile: Profile,
+    avatar: Option<image::Handle>,
+    color: Option<Color>,
+}
+
+enum AttachmentView {
+    Loading,
+    Loaded {
+        attachment: Attachment,
+        image: Option<image::Handle>,
+    },
+    Failed(String),
+}
+
 struct State {
     happ: Arc<Happ>,
-    error_message: Option<String>,
+    /// Conductor directory, where the settings are stored.
+    holochain_dir: PathBuf,
+    settings: UserSettings,
+    /// Toasts about failures and other events, shown below everything else.
+    notifications: Notifications<Message>,
     nickname: String,
+    /// Result of checking `nickname`, `None` while unchecked.
+    nickname_problem: Option<Option<NicknameProblem>>,
+    creating_profile: bool,
     profile: Option<Profile>,
+    screen: Screen,
     loading_profile: bool,
     loading_messages: bool,
-    saving_message: bool,
+    /// Messages sent from the composer in this session, with their delivery
+    /// status.
+    outbox: Outbox,
+    /// Sent messages whose delivery to other agents is being checked.
+    checking_delivery: HashSet<ActionHash>,
     current_message: String,
-    holo_messages: Vec<HoloMessage>,
+    encrypt_message: bool,
+    message_expiry: MessageExpiry,
+    uploading_attachment: bool,
+    /// File name and hash of the attachment of the message being written.
+    current_attachment: Option<(String, EntryHash)>,
+    /// Loaded pages of the feed, newest first.
+    holo_messages: Vec<FeedMessage>,
+    /// Cursor of the next older page of the feed, `None` once all are loaded.
+    older_messages: Option<MessagesCursor>,
+    loading_older_messages: bool,
+    /// Scroll position of the feed from its top.
+    feed_offset: f32,
+    /// New messages keep the feed scrolled to the bottom if it was there.
+    feed_at_bottom: bool,
+    window_height: f32,
+    attachments: HashMap<EntryHash, AttachmentView>,
+    mentions: Vec<FeedMessage>,
+    direct_messages: Vec<DirectMessageItem>,
+    selected_peer: Option<AgentPubKey>,
+    new_peer: String,
+    sending_direct_message: bool,
+    current_direct_message: String,
+    encrypt_direct_message: bool,
+    undelivered_messages: HashSet<ActionHash>,
+    read_markers: HashMap<String, ReadMarker>,
+    read_markers_loaded: bool,
+    /// Messages after this point are shown above a "new messages" divider.
+    new_messages_since: Option<Timestamp>,
+    last_typing_notification: Option<Instant>,
+    /// Nicknames of agents typing right now and when they were last seen typing.
+    typing: HashMap<AgentPubKey, (String, Instant)>,
+    search_text: String,
+    search_author: String,
+    search_since: String,
+    search_until: String,
+    searching: bool,
+    search_results: Option<Vec<SearchHit>>,
+    /// Message jumped to from the search results.
+    highlighted_message: Option<ActionHash>,
+    /// Message the feed pages back to, until it is loaded.
+    scroll_target: Option<ActionHash>,
+    /// Show message texts as written instead of rendering their markdown.
+    show_raw_markdown: bool,
+    dna_properties: Option<HoloMessageProperties>,
+    pinned: Vec<FeedMessage>,
+    pinned_expanded: bool,
+    moderation: Moderation,
+    creating_invite: bool,
+    invite_code: Option<String>,
+    /// Avatar of this agent's profile.
+    avatar: Option<image::Handle>,
+    /// Profiles of message authors and members.
+    authors: HashMap<AgentPubKey, AuthorView>,
+    /// Members matching `member_search`, by nickname.
+    members: Vec<AgentPubKey>,
+    member_search: String,
+    loading_members: bool,
+    /// Agents that are online or away, others are offline.
+    presence: HashMap<AgentPubKey, PresenceStatus>,
+    /// Last keyboard or mouse input, to tell whether this agent is away.
+    last_activity: Instant,
+    /// Status and time of this agent's last heartbeat.
+    announced_presence: Option<(PresenceStatus, Instant)>,
+    edited_nickname: String,
+    edited_bio: String,
+    edited_color: String,
+    edited_avatar: Option<Vec<u8>>,
+    loading_avatar: bool,
+    saving_profile: bool,
+    polls: Vec<PollResults>,
+    composing_poll: bool,
+    poll_question: String,
+    poll_options: Vec<String>,
+    poll_duration: PollDuration,
+    creating_poll: bool,
+    /// Polls with a vote being cast.
+    voting: HashSet<ActionHash>,
 }
 
 impl State {
-    pub fn new(happ: Arc<Happ>) -> Self {
+    pub fn new(happ: Arc<Happ>, holochain_dir: PathBuf, settings: UserSettings) -> Self {
         State {
             happ,
-            error_message: None,
+            holochain_dir,
+            settings,
+            notifications: Notifications::default(),
             nickname: String::new(),
+            // nothing entered yet
+            nickname_problem: Some(Some(NicknameProblem::TooShort)),
+            creating_profile: false,
             profile: None,
             loading_profile: false,
             loading_messages: false,
-            saving_message: false,
+            outbox: Outbox::default(),
+            checking_delivery: HashSet::new(),
             current_message: String::new(),
+            encrypt_message: false,
+            message_expiry: MessageExpiry::default(),
+            uploading_attachment: false,
+            current_attachment: None,
             holo_messages: Vec::new(),
+            older_messages: None,
+            loading_older_messages: false,
+            feed_offset: 0.0,
+            feed_at_bottom: true,
+            window_height: DEFAULT_WINDOW_HEIGHT,
+            attachments: HashMap::new(),
+            mentions: Vec::new(),
+            screen: Screen::Feed,
+            direct_messages: Vec::new(),
+            selected_peer: None,
+            new_peer: String::new(),
+            sending_direct_message: false,
+            current_direct_message: String::new(),
+            encrypt_direct_message: false,
+            undelivered_messages: HashSet::new(),
+            read_markers: HashMap::new(),
+            read_markers_loaded: false,
+            new_messages_since: None,
+            last_typing_notification: None,
+            typing: HashMap::new(),
+            search_text: String::new(),
+            search_author: String::new(),
+            search_since: String::new(),
+            search_until: String::new(),
+            searching: false,
+            search_results: None,
+            highlighted_message: None,
+            scroll_target: None,
+            show_raw_markdown: false,
+            dna_properties: None,
+            pinned: Vec::new(),
+            pinned_expanded: false,
+            moderation: Moderation::default(),
+            creating_invite: false,
+            invite_code: None,
+            avatar: None,
+            authors: HashMap::new(),
+            members: Vec::new(),
+            member_search: String::new(),
+            loading_members: false,
+            presence: HashMap::new(),
+            last_activity: Instant::now(),
+            announced_presence: None,
+            edited_nickname: String::new(),
+            edited_bio: String::new(),
+            edited_color: String::new(),
+            edited_avatar: None,
+            loading_avatar: false,
+            saving_profile: false,
+            polls: Vec::new(),
+            composing_poll: false,
+            poll_question: String::new(),
+            poll_options: vec![String::new(), String::new()],
+            poll_duration: PollDuration::default(),
+            creating_poll: false,
+            voting: HashSet::new(),
+        }
+    }
+
+    /// Show a notification. Errors stay until they are dismissed, `retry`
+    /// repeats the action that failed.
+    fn notify(
+        &mut self,
+        severity: Severity,
+        summary: impl Into<String>,
+        details: Option<String>,
+        retry: Option<Message>,
+    ) {
+        let summary = summary.into();
+        match &details {
+            Some(details) => eprintln!("{summary}: {details}"),
+            None => eprintln!("{summary}"),
+        }
+        self.notifications
+            .push(severity, summary, details, retry, Instant::now());
+    }
+
+    /// Store the settings after a change, which already applies.
+    fn save_settings(&self) -> Command<Message> {
+        Command::perform(
+            save_settings(self.holochain_dir.clone(), self.settings.clone()),
+            Message::SettingsSaved,
+        )
+    }
+
+    /// Authors of the most recent feed messages, except this agent.
+    fn recent_participants(&self) -> Vec<AgentPubKey> {
+        let mut participants = Vec::new();
+        for holo_mess in self.holo_messages.iter().take(RECENT_PARTICIPANTS_WINDOW) {
+            if &holo_mess.author != self.my_agent_key() && !participants.contains(&holo_mess.author)
+            {
+                participants.push(holo_mess.author.clone());
+            }
+        }
+        participants
+    }
+
+    /// Notify recent participants that this agent is typing, at most once per
+    /// notification interval.
+    fn notify_typing(&mut self) -> Command<Message> {
+        let throttled = self
+            .last_typing_notification
+            .map_or(false, |sent| sent.elapsed() < TYPING_NOTIFICATION_INTERVAL);
+        let participants = self.recent_participants();
+        match &self.profile {
+            Some(profile) if !throttled && !participants.is_empty() => {
+                self.last_typing_notification = Some(Instant::now());
+                Command::perform(
+                    notify_typing(self.happ.clone(), participants, profile.nickname.clone()),
+                    Message::TypingNotified,
+                )
+            }
+            _ => Command::none(),
+        }
+    }
+
+    fn last_seen_at(&self, channel: &str) -> Option<Timestamp> {
+        self.read_markers
+            .get(channel)
+            .map(|read_marker| read_marker.last_seen_at)
+    }
+
+    fn unread_feed_count(&self) -> usize {
+        let last_seen_at = self.last_seen_at(FEED_CHANNEL);
+        self.holo_messages
+            .iter()
+            .filter(|m| &m.author != self.my_agent_key() && Some(m.timestamp) > last_seen_at)
+            .count()
+    }
+
+    fn unread_direct_message_count(&self, peer: &AgentPubKey) -> usize {
+        let last_seen_at = self.last_seen_at(&direct_message_channel(peer));
+        self.direct_messages
+            .iter()
+            .filter(|item| &item.direct_message.from == peer && Some(item.timestamp) > last_seen_at)
+            .count()
+    }
+
+    fn unread_direct_messages_count(&self) -> usize {
+        self.conversations()
+            .iter()
+            .map(|peer| self.unread_direct_message_count(peer))
+            .sum()
+    }
+
+    /// Move the read marker of the channel on screen to its latest message.
+    fn mark_viewed(&mut self) -> Command<Message> {
+        if !self.read_markers_loaded {
+            return Command::none();
+        }
+        let latest = match self.screen {
+            Screen::Feed => self
+                .holo_messages
+                .iter()
+                .max_by_key(|m| m.timestamp)
+                .map(|m| (FEED_CHANNEL.to_string(), m.action_hash.clone(), m.timestamp)),
+            Screen::Mentions | Screen::Search => None,
+            Screen::DirectMessages => self.selected_peer.as_ref().and_then(|peer| {
+                self.direct_messages
+                    .iter()
+                    .filter(|item| item.direct_message.peer(self.my_agent_key()) == peer)
+                    .max_by_key(|item| item.timestamp)
+                    .map(|item| {
+                        (
+                            direct_message_channel(peer),
+                            item.action_hash.clone(),
+                            item.timestamp,
+                        )
+                    })
+            }),
+        };
+        let Some((channel, last_seen, last_seen_at)) = latest else {
+            return Command::none();
+        };
+        if self.last_seen_at(&channel) >= Some(last_seen_at) {
+            return Command::none();
+        }
+        let read_marker = ReadMarker {
+            channel: channel.clone(),
+            last_seen,
+            last_seen_at,
+        };
+        self.read_markers.insert(channel, read_marker.clone());
+        Command::perform(
+            set_read_marker(self.happ.clone(), read_marker),
+            Message::ReadMarkerSet,
+        )
+    }
+
+    /// Put the newest page of the feed in place of the messages it covers,
+    /// keeping older pages that were loaded.
+    fn merge_latest_page(&mut self, page: FeedPage) {
+        let older = page.older_loaded(std::mem::take(&mut self.holo_messages));
+        if older.is_empty() {
+            self.older_messages = page.older;
+        }
+        self.holo_messages = page.messages;
+        self.holo_messages.extend(older);
+    }
+
+    fn load_older_messages(&mut self) -> Command<Message> {
+        match &self.older_messages {
+            Some(before) if !self.loading_older_messages => {
+                self.loading_older_messages = true;
+                Command::perform(
+                    fetch_messages_page(self.happ.clone(), Some(before.clone())),
+                    Message::OlderMessagesFetched,
+                )
+            }
+            _ => Command::none(),
+        }
+    }
+
+    /// Create an outgoing message in the feed.
+    fn send_outgoing(&self, id: u64) -> Command<Message> {
+        let Some(outgoing) = self.outbox.get(id) else {
+            return Command::none();
+        };
+        Command::perform(
+            create_message(
+                self.happ.clone(),
+                outgoing.text.clone(),
+                outgoing.encrypt,
+                outgoing
+                    .attachment
+                    .as_ref()
+                    .map(|(_, attachment_hash)| attachment_hash.clone()),
+                outgoing.expires_at,
+            ),
+            move |result| Message::HoloMessageCreated(id, result),
+        )
+    }
+
+    fn scroll_feed_to_bottom(&mut self) -> Command<Message> {
+        let height: f32 = feed_rows(self)
+            .iter()
+            .map(|row| estimated_height(self, row))
+            .sum();
+        self.feed_offset = (height - self.window_height).max(0.0);
+        self.feed_at_bottom = true;
+        scrollable::snap_to(
+            FEED_SCROLLABLE_ID.clone(),
+            scrollable::RelativeOffset { x: 0.0, y: 1.0 },
+        )
+    }
+
+    /// Scroll the feed to a message, loading older pages until it is there.
+    fn scroll_to_message(&mut self, action_hash: &ActionHash) -> Command<Message> {
+        let mut top = 0.0;
+        let mut offset = None;
+        for row in feed_rows(self) {
+            if matches!(row, FeedRow::Message(holo_mess, _) if &holo_mess.action_hash == action_hash)
+            {
+                offset = Some(top);
+                break;
+            }
+            top += estimated_height(self, &row);
+        }
+        let Some(offset) = offset else {
+            if self.older_messages.is_some() {
+                self.scroll_target = Some(action_hash.clone());
+                return self.load_older_messages();
+            }
+            // hidden, blocked or expired since it was indexed
+            self.scroll_target = None;
+            self.notify(
+                Severity::Warning,
+                "the message is no longer in the feed",
+                None,
+                None,
+            );
+            return Command::none();
+        };
+        self.scroll_target = None;
+        self.feed_offset = offset;
+        self.feed_at_bottom = false;
+        scrollable::scroll_to(
+            FEED_SCROLLABLE_ID.clone(),
+            scrollable::AbsoluteOffset { x: 0.0, y: offset },
+        )
+    }
+
+    fn fetch_new_attachments(&mut self) -> Command<Message> {
+        let new_attachments: Vec<EntryHash> = self
+            .holo_messages
+            .iter()
+            .filter_map(|holo_mess| holo_mess.attachment.clone())
+            .filter(|attachment_hash| !self.attachments.contains_key(attachment_hash))
+            .collect();
+        let mut commands = Vec::with_capacity(new_attachments.len());
+        for attachment_hash in new_attachments {
+            self.attachments
+                .insert(attachment_hash.clone(), AttachmentView::Loading);
+            commands.push(Command::perform(
+                fetch_attachment(self.happ.clone(), attachment_hash.clone()),
+                move |result| Message::AttachmentFetched(attachment_hash, result),
+            ));
+        }
+        Command::batch(commands)
+    }
+
+    /// Whether a message mentions this agent's nickname.
+    fn mentions_me(&self, holo_mess: &FeedMessage) -> bool {
+        match (&self.profile, &holo_mess.text) {
+            (Some(profile), Some(text)) => {
+                mentioned_nicknames(text).contains(&profile.nickname.as_str())
+            }
+            _ => false,
+        }
+    }
+
+    /// Search query from the search form. Dates are inclusive, the until date
+    /// covers the whole day.
+    fn search_query(&self) -> Result<SearchQuery, String> {
+        let author = match self.search_author.trim() {
+            "" => None,
+            author => Some(
+                AgentPubKeyB64::from_b64_str(author)
+                    .map_err(|err| format!("invalid author key: {err}"))?
+                    .into(),
+            ),
+        };
+        Ok(SearchQuery {
+            text: self.search_text.clone(),
+            author,
+            since: parse_search_date(&self.search_since, 0)?,
+            until: parse_search_date(&self.search_until, 1)?,
+        })
+    }
+
+    fn search(&mut self) -> Command<Message> {
+        match self.search_query() {
+            Ok(query) => {
+                self.searching = true;
+                Command::perform(
+                    search_messages(self.happ.clone(), query),
+                    Message::SearchResultsFetched,
+                )
+            }
+            Err(err) => {
+                self.notify(Severity::Warning, err, None, None);
+                Command::none()
+            }
+        }
+    }
+
+    fn is_progenitor(&self) -> bool {
+        self.dna_properties.as_ref().map_or(false, |properties| {
+            properties.is_progenitor(self.my_agent_key())
+        })
+    }
+
+    fn is_moderator(&self) -> bool {
+        self.moderation.moderators.contains(self.my_agent_key())
+    }
+
+    fn may_pin(&self) -> bool {
+        match self.dna_properties.as_ref().map(|p| p.pin_permission) {
+            Some(PinPermission::Anyone) => true,
+            Some(PinPermission::Moderators) => self.is_moderator(),
+            None => false,
+        }
+    }
+
+    fn is_hidden(&self, holo_mess: &FeedMessage) -> bool {
+        self.moderation
+            .hidden_messages
+            .contains(&holo_mess.action_hash)
+    }
+
+    fn is_blocked(&self, agent: &AgentPubKey) -> bool {
+        self.moderation.blocked_agents.contains(agent)
+    }
+
+    /// Expired messages are hidden until their author's node deletes them.
+    /// Hidden messages and messages of blocked agents are only shown to
+    /// moderators. This is the only place hiding and blocking take effect,
+    /// the messages are still valid and stored in the DHT.
+    fn is_visible(&self, holo_mess: &FeedMessage) -> bool {
+        !holo_mess.is_expired(Timestamp::now())
+            && (self.is_moderator()
+                || !(self.is_hidden(holo_mess) || self.is_blocked(&holo_mess.author)))
+    }
+
+    fn has_expiring_messages(&self) -> bool {
+        self.holo_messages
+            .iter()
+            .chain(self.mentions.iter())
+            .any(|holo_mess| holo_mess.expires_at.is_some())
+    }
+
+    fn set_profile(&mut self, profile: Option<Profile>) {
+        self.avatar = profile
+            .as_ref()
+            .and_then(|profile| ProfileDetails::of(profile).avatar)
+            .map(image::Handle::from_memory);
+        self.profile = profile;
+    }
+
+    /// Fetch profiles of authors shown in the message lists. `Happ` caches
+    /// them, so only new authors cause a zome call.
+    fn fetch_author_profiles(&self) -> Command<Message> {
+        let mut authors: Vec<AgentPubKey> = self
+            .holo_messages
+            .iter()
+            .chain(self.mentions.iter())
+            .chain(self.pinned.iter())
+            .map(|holo_mess| holo_mess.author.clone())
+            .chain(self.polls.iter().map(|poll| poll.author.clone()))
+            .collect();
+        authors.sort();
+        authors.dedup();
+        Command::perform(
+            fetch_profiles(self.happ.clone(), authors),
+            Message::AuthorProfilesFetched,
+        )
+    }
+
+    fn insert_authors(&mut self, profiles: HashMap<AgentPubKey, Profile>) {
+        for (agent, profile) in profiles {
+            let is_unchanged = self
+                .authors
+                .get(&agent)
+                .map_or(false, |author| author.profile == profile);
+            if !is_unchanged {
+                let details = ProfileDetails::of(&profile);
+                let author_view = AuthorView {
+                    avatar: details.avatar.map(image::Handle::from_memory),
+                    color: details.color.map(|[r, g, b]| Color::from_rgb8(r, g, b)),
+                    profile,
+                };
+                self.authors.insert(agent, author_view);
+            }
+        }
+    }
+
+    fn search_members(&mut self) -> Command<Message> {
+        self.loading_members = true;
+        let member_search = self.member_search.clone();
+        Command::perform(
+            search_members(self.happ.clone(), member_search.clone()),
+            move |result| Message::MembersFetched(member_search, result),
+        )
+    }
+
+    fn presence_status(&self) -> PresenceStatus {
+        if self.last_activity.elapsed() < AWAY_AFTER {
+            PresenceStatus::Online
+        } else {
+            PresenceStatus::Away
+        }
+    }
+
+    fn announce_presence(&mut self) -> Command<Message> {
+        let status = self.presence_status();
+        self.announced_presence = Some((status, Instant::now()));
+        Command::perform(
+            announce_presence(self.happ.clone(), status),
+            Message::PresenceAnnounced,
+        )
+    }
+
+    /// Announce this agent's presence when its status changed or the next
+    /// heartbeat is due.
+    fn refresh_presence(&mut self) -> Command<Message> {
+        match self.announced_presence {
+            Some((status, announced_at))
+                if status == self.presence_status()
+                    && announced_at.elapsed() < PRESENCE_HEARTBEAT_INTERVAL =>
+            {
+                Command::none()
+            }
+            _ => self.announce_presence(),
+        }
+    }
+
+    /// A profile can be created once its nickname has passed the checks.
+    fn may_create_profile(&self) -> bool {
+        !self.creating_profile && self.nickname_problem == Some(None)
+    }
+
+    /// Start editing from the current profile.
+    fn reset_profile_edits(&mut self) {
+        let Some(profile) = &self.profile else {
+            return;
+        };
+        let details = ProfileDetails::of(profile);
+        self.edited_nickname = profile.nickname.clone();
+        self.edited_bio = details.bio;
+        self.edited_color = details.color.map(format_color).unwrap_or_default();
+        self.edited_avatar = details.avatar;
+    }
+
+    /// The profile can be saved with a nickname and an empty or valid color.
+    fn may_save_profile(&self) -> bool {
+        !self.saving_profile
+            && !self.loading_avatar
+            && !self.edited_nickname.trim().is_empty()
+            && (self.edited_color.trim().is_empty() || parse_color(&self.edited_color).is_some())
+    }
+
+    fn profile_color(&self) -> Option<Color> {
+        let [r, g, b] = ProfileDetails::of(self.profile.as_ref()?).color?;
+        Some(Color::from_rgb8(r, g, b))
+    }
+
+    fn may_submit_poll(&self) -> bool {
+        !self.creating_poll
+            && !self.poll_question.trim().is_empty()
+            && self
+                .poll_options
+                .iter()
+                .filter(|option| !option.trim().is_empty())
+                .count()
+                >= 2
+    }
+
+    fn is_pinned(&self, message_hash: &ActionHash) -> bool {
+        self.pinned
+            .iter()
+            .any(|pinned| &pinned.action_hash == message_hash)
+    }
+
+    fn my_agent_key(&self) -> &AgentPubKey {
+        self.happ.cell_id.agent_pubkey()
+    }
+
+    /// Peers of all direct message conversations, most recently active first.
+    fn conversations(&self) -> Vec<AgentPubKey> {
+        let mut peers = VecDeque::new();
+        for item in self.direct_messages.iter() {
+            let peer = item.direct_message.peer(self.my_agent_key()).clone();
+            peers.retain(|p| p != &peer);
+            peers.push_front(peer);
+        }
+        if let Some(selected_peer) = &self.selected_peer {
+            if !peers.contains(selected_peer) {
+                peers.push_front(selected_peer.clone());
+            }
         }
+        peers.into()
     }
 }
 
@@ -81,13 +1010,115 @@ impl State {
 enum Message {
     HappStarted(Result<Happ, String>),
     NicknameChanged(String),
+    /// Outcome of checking a nickname for a new profile.
+    NicknameChecked(String, Result<Option<NicknameProblem>, String>),
     ProfileFetched(Result<Option<Profile>, String>),
     CreateProfile,
     ProfileCreated(Result<Profile, String>),
+    EditedNicknameChanged(String),
+    EditedBioChanged(String),
+    EditedColorChanged(String),
+    PickAvatar,
+    AvatarPicked(Option<PathBuf>),
+    AvatarLoaded(Result<Vec<u8>, String>),
+    RemoveAvatar,
+    SaveProfile,
+    ProfileUpdated(Result<Profile, String>),
+    AuthorProfilesFetched(Result<HashMap<AgentPubKey, Profile>, String>),
     HoloMessageChanged(String),
+    EncryptMessageToggled(bool),
+    MessageExpiryChanged(MessageExpiry),
+    PickAttachment,
+    AttachmentPicked(Option<PathBuf>),
+    AttachmentUploaded(Result<(String, EntryHash), String>),
+    RemoveAttachment,
+    AttachmentFetched(EntryHash, Result<(Attachment, Option<Vec<u8>>), String>),
+    SaveAttachment(Attachment),
+    AttachmentSaved(Result<Option<PathBuf>, String>),
     CreateHoloMessage,
-    HoloMessageCreated(Result<ActionHash, String>),
-    HoloMessagesFetched(Result<Vec<HoloMessage>, String>),
+    /// Outcome of creating an outgoing message.
+    HoloMessageCreated(u64, Result<ActionHash, String>),
+    RetryOutgoing(u64),
+    DiscardOutgoing(u64),
+    /// Ask other agents whether they got the messages sent.
+    CheckDelivery,
+    DeliveryChecked(ActionHash, Result<bool, String>),
+    /// The newest page of the feed.
+    HoloMessagesFetched(Result<FeedPage, String>),
+    OlderMessagesFetched(Result<FeedPage, String>),
+    LoadOlderMessages,
+    FeedScrolled(scrollable::Viewport),
+    WindowResized(u32),
+    ShowScreen(Screen),
+    DirectMessagesFetched(Result<Vec<DirectMessageItem>, String>),
+    MentionsFetched(Result<Vec<FeedMessage>, String>),
+    SelectConversation(AgentPubKey),
+    NewPeerChanged(String),
+    StartConversation,
+    DirectMessageChanged(String),
+    EncryptDirectMessageToggled(bool),
+    SendDirectMessage,
+    DirectMessageSent(Result<DirectMessageSent, String>),
+    ReadMarkersFetched(Result<Vec<ReadMarker>, String>),
+    ReadMarkerSet(Result<ActionHash, String>),
+    TypingNotified(Result<(), String>),
+    SignalReceived(HoloMessageSignal),
+    Tick(Instant),
+    SearchTextChanged(String),
+    SearchAuthorChanged(String),
+    SearchSinceChanged(String),
+    SearchUntilChanged(String),
+    Search,
+    SearchResultsFetched(Result<Vec<SearchHit>, String>),
+    ShowSearchHit(SearchHit),
+    ShowRawMarkdownToggled(bool),
+    DnaPropertiesFetched(Result<HoloMessageProperties, String>),
+    PinnedFetched(Result<Vec<FeedMessage>, String>),
+    PinMessage(ActionHash),
+    UnpinMessage(ActionHash),
+    PinChanged(Result<(), String>),
+    TogglePinned,
+    ModerationFetched(Result<Moderation, String>),
+    GrantModerator(AgentPubKey),
+    HideMessage(ActionHash),
+    UnhideMessage(ActionHash),
+    BlockAgent(AgentPubKey),
+    UnblockAgent(AgentPubKey),
+    ModerationChanged(Result<(), String>),
+    CreateInvite,
+    InviteCreated(Result<String, String>),
+    CopyInviteCode,
+    TogglePollComposer,
+    PollQuestionChanged(String),
+    PollOptionChanged(usize, String),
+    AddPollOption,
+    RemovePollOption(usize),
+    PollDurationChanged(PollDuration),
+    CreatePoll,
+    PollCreated(Result<ActionHash, String>),
+    Vote(ActionHash, u32),
+    Voted(ActionHash, Result<ActionHash, String>),
+    PollsFetched(Result<Vec<PollResults>, String>),
+    MemberSearchChanged(String),
+    /// Members found for a search text.
+    MembersFetched(String, Result<HashMap<AgentPubKey, Profile>, String>),
+    MessageMember(AgentPubKey),
+    ShowPostsOf(AgentPubKey),
+    AnnouncePresence,
+    PresenceAnnounced(Result<(), String>),
+    PresenceChanged(PresenceChange),
+    UserActive,
+    ThemeChanged(ThemeChoice),
+    CustomColorChanged(PaletteColor, String),
+    FontScaleChanged(f32),
+    TimestampFormatChanged(TimestampFormat),
+    EnterToSendToggled(bool),
+    UnreadCountInTitleToggled(bool),
+    TypingIndicatorsToggled(bool),
+    SettingsSaved(Result<(), String>),
+    DismissNotification(u64),
+    RetryNotification(u64),
+    ToggleNotificationDetails(u64),
 }
 
 impl Application for Holomess {
@@ -97,31 +1128,73 @@ impl Application for Holomess {
     type Theme = Theme;
 
     fn new(flags: Self::Flags) -> (Self, Command<Message>) {
-        let holochain_dir = match flags {
-            Flags::HolochainDir(holochain_dir) => holochain_dir,
+        let (holochain_dir, invite_code) = match flags {
+            Flags::HolochainDir(holochain_dir, invite_code) => (holochain_dir, invite_code),
         };
+        let settings = UserSettings::load(&holochain_dir).unwrap_or_else(|err| {
+            eprintln!("settings couldn't be loaded, using defaults: {err}");
+            UserSettings::default()
+        });
         (
-            Holomess::Starting,
-            Command::perform(start_happ(holochain_dir), Message::HappStarted),
+            Holomess::Starting(holochain_dir.clone(), settings),
+            Command::perform(start_happ(holochain_dir, invite_code), Message::HappStarted),
         )
     }
 
     fn title(&self) -> String {
-        "Holochain on iced".to_string()
+        let unread = match self {
+            Holomess::Running(state) if state.settings.notifications.unread_count_in_title => {
+                state.unread_feed_count() + state.unread_direct_messages_count()
+            }
+            _ => 0,
+        };
+        if unread > 0 {
+            format!("Holochain on iced ({unread})")
+        } else {
+            "Holochain on iced".to_string()
+        }
+    }
+
+    fn theme(&self) -> Theme {
+        let settings = match self {
+            Holomess::Starting(_, settings) => settings,
+            Holomess::Running(state) => &state.settings,
+            Holomess::Failed(_) => return Theme::default(),
+        };
+        match &settings.theme {
+            ThemeSetting::Light => Theme::Light,
+            ThemeSetting::Dark => Theme::Dark,
+            ThemeSetting::Custom(palette) => Theme::custom(custom_palette(palette)),
+        }
+    }
+
+    fn scale_factor(&self) -> f64 {
+        match self {
+            Holomess::Starting(_, settings) => settings.font_scale.into(),
+            Holomess::Running(state) => state.settings.font_scale.into(),
+            Holomess::Failed(_) => 1.0,
+        }
     }
 
     fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
         match self {
-            Holomess::Starting => {
+            Holomess::Starting(holochain_dir, settings) => {
                 println!("starting up HoloMessage...");
                 let command = match message {
                     Message::HappStarted(Ok(happ)) => {
                         println!("happ started; fetching profile...");
                         let happ = Arc::new(happ);
-                        let mut state = State::new(happ.clone());
+                        let mut state =
+                            State::new(happ.clone(), holochain_dir.clone(), settings.clone());
                         state.loading_profile = true;
                         *self = Holomess::Running(state);
-                        Command::perform(fetch_profile(happ), Message::ProfileFetched)
+                        Command::batch(vec![
+                            Command::perform(fetch_profile(happ.clone()), Message::ProfileFetched),
+                            Command::perform(
+                                fetch_dna_properties(happ),
+                                Message::DnaPropertiesFetched,
+                            ),
+                        ])
                     }
                     Message::HappStarted(Err(err)) => {
                         eprintln!("error starting happ - {err}");
@@ -136,16 +1209,18 @@ impl Application for Holomess {
                 let command = match message {
                     Message::ProfileFetched(Ok(maybe_profile)) => {
                         state.loading_profile = false;
-                        state.profile = maybe_profile;
+                        state.set_profile(maybe_profile);
                         if let Some(profile) = &state.profile {
-                            println!("profile fetched is {profile:?}");
+                            println!("profile of {} fetched", profile.nickname);
                             state.loading_messages = true;
                             Command::batch(vec![
+                                state.announce_presence(),
                                 // focus message input field
                                 text_input::focus::<Message>(MESSAGE_INPUT_ID.clone()),
+                                // messages are fetched once the read markers are known
                                 Command::perform(
-                                    fetch_messages(state.happ.clone()),
-                                    Message::HoloMessagesFetched,
+                                    fetch_read_markers(state.happ.clone()),
+                                    Message::ReadMarkersFetched,
                                 ),
                             ])
                         } else {
@@ -155,74 +1230,991 @@ impl Application for Holomess {
                     }
                     Message::ProfileFetched(Err(err)) => {
                         state.loading_profile = false;
-                        eprintln!("profile couldn't be fetched: {err}");
-                        state.error_message = Some(format!("profile couldn't be fetched: {err}"));
+                        state.notify(
+                            Severity::Error,
+                            "profile couldn't be fetched",
+                            Some(err),
+                            None,
+                        );
                         Command::none()
                     }
                     Message::NicknameChanged(nickname) => {
                         state.nickname = nickname;
+                        match check_nickname_length(&state.nickname) {
+                            Some(problem) => {
+                                state.nickname_problem = Some(Some(problem));
+                                Command::none()
+                            }
+                            None => {
+                                state.nickname_problem = None;
+                                let nickname = state.nickname.clone();
+                                Command::perform(
+                                    check_nickname(state.happ.clone(), nickname.clone()),
+                                    move |result| Message::NicknameChecked(nickname, result),
+                                )
+                            }
+                        }
+                    }
+                    Message::NicknameChecked(nickname, result) => {
+                        // results for an outdated nickname are dropped
+                        if nickname == state.nickname {
+                            state.nickname_problem = Some(result.unwrap_or_else(|err| {
+                                // the profiles zome has the final word on creation
+                                eprintln!("nickname couldn't be checked: {err}");
+                                None
+                            }));
+                        }
                         Command::none()
                     }
                     Message::HoloMessageChanged(message) => {
+                        let command = if message.is_empty() {
+                            Command::none()
+                        } else {
+                            state.notify_typing()
+                        };
                         state.current_message = message;
+                        command
+                    }
+                    Message::EncryptMessageToggled(encrypt) => {
+                        state.encrypt_message = encrypt;
+                        Command::none()
+                    }
+                    Message::MessageExpiryChanged(message_expiry) => {
+                        state.message_expiry = message_expiry;
+                        Command::none()
+                    }
+                    Message::CreateHoloMessage
+                        if state.current_message.trim().is_empty()
+                            && state.current_attachment.is_none() =>
+                    {
                         Command::none()
                     }
                     Message::CreateHoloMessage => {
-                        println!("creating new message {:?}", state.current_message);
-                        state.saving_message = true;
+                        // the composer is free for the next message right away
+                        let id = state
+                            .outbox
+                            .push(
+                                std::mem::take(&mut state.current_message),
+                                state.encrypt_message,
+                                state.current_attachment.take(),
+                                state.message_expiry.expires_at(),
+                                Timestamp::now(),
+                            )
+                            .id;
+                        // the own message shows up at the bottom
+                        Command::batch(vec![state.send_outgoing(id), state.scroll_feed_to_bottom()])
+                    }
+                    Message::HoloMessageCreated(id, Ok(action_hash)) => {
+                        println!("created a new message {action_hash:?}, fetching newest messages");
+                        state
+                            .outbox
+                            .set_status(id, DeliveryStatus::Committed(action_hash));
                         Command::perform(
-                            create_message(state.happ.clone(), state.current_message.clone()),
-                            Message::HoloMessageCreated,
+                            fetch_messages_page(state.happ.clone(), None),
+                            Message::HoloMessagesFetched,
                         )
                     }
-                    Message::HoloMessageCreated(Ok(message)) => {
-                        state.saving_message = false;
-                        println!("created a new message {message:?}, fetching all messages");
-                        state.current_message = "".to_string();
-
-                        state.loading_messages = true;
-                        Command::batch(vec![
-                            // focus message input field
-                            text_input::focus::<Message>(MESSAGE_INPUT_ID.clone()),
+                    Message::HoloMessageCreated(id, Err(err)) => {
+                        state
+                            .outbox
+                            .set_status(id, DeliveryStatus::Failed(err.clone()));
+                        state.notify(
+                            Severity::Warning,
+                            "message couldn't be sent",
+                            Some(err),
+                            Some(Message::RetryOutgoing(id)),
+                        );
+                        Command::none()
+                    }
+                    Message::RetryOutgoing(id) => {
+                        if state.outbox.retry(id).is_some() {
+                            state.send_outgoing(id)
+                        } else {
+                            Command::none()
+                        }
+                    }
+                    Message::DiscardOutgoing(id) => {
+                        state.outbox.discard(id);
+                        Command::none()
+                    }
+                    Message::CheckDelivery => {
+                        let unchecked: Vec<ActionHash> = state
+                            .outbox
+                            .delivery_checks_due(Timestamp::now())
+                            .filter(|action_hash| !state.checking_delivery.contains(*action_hash))
+                            .cloned()
+                            .collect();
+                        let commands = unchecked.into_iter().map(|action_hash| {
+                            state.checking_delivery.insert(action_hash.clone());
                             Command::perform(
-                                fetch_messages(state.happ.clone()),
-                                Message::HoloMessagesFetched,
-                            ),
-                        ])
+                                is_message_seen(state.happ.clone(), action_hash.clone()),
+                                move |result| Message::DeliveryChecked(action_hash, result),
+                            )
+                        });
+                        Command::batch(commands.collect::<Vec<_>>())
+                    }
+                    Message::DeliveryChecked(action_hash, result) => {
+                        state.checking_delivery.remove(&action_hash);
+                        match result {
+                            Ok(true) => state.outbox.mark_seen(&action_hash),
+                            Ok(false) => state
+                                .outbox
+                                .delivery_unconfirmed(&action_hash, Timestamp::now()),
+                            Err(err) => {
+                                state
+                                    .outbox
+                                    .delivery_unconfirmed(&action_hash, Timestamp::now());
+                                state.notify(
+                                    Severity::Warning,
+                                    "message delivery couldn't be checked",
+                                    Some(err),
+                                    None,
+                                );
+                            }
+                        }
+                        Command::none()
+                    }
+                    Message::PickAttachment => {
+                        Command::perform(pick_file(), Message::AttachmentPicked)
+                    }
+                    Message::AttachmentPicked(Some(path)) => {
+                        println!("uploading attachment {path:?}");
+                        state.uploading_attachment = true;
+                        Command::perform(
+                            upload_file(state.happ.clone(), path),
+                            Message::AttachmentUploaded,
+                        )
+                    }
+                    Message::AttachmentPicked(None) => Command::none(),
+                    Message::AttachmentUploaded(Ok(attachment)) => {
+                        state.uploading_attachment = false;
+                        state.current_attachment = Some(attachment);
+                        text_input::focus::<Message>(MESSAGE_INPUT_ID.clone())
+                    }
+                    Message::AttachmentUploaded(Err(err)) => {
+                        state.uploading_attachment = false;
+                        state.notify(
+                            Severity::Error,
+                            "file couldn't be uploaded",
+                            Some(err),
+                            None,
+                        );
+                        Command::none()
+                    }
+                    Message::RemoveAttachment => {
+                        state.current_attachment = None;
+                        Command::none()
+                    }
+                    Message::AttachmentFetched(attachment_hash, Ok((attachment, content))) => {
+                        let image = content.map(image::Handle::from_memory);
+                        state.attachments.insert(
+                            attachment_hash,
+                            AttachmentView::Loaded { attachment, image },
+                        );
+                        Command::none()
+                    }
+                    Message::AttachmentFetched(attachment_hash, Err(err)) => {
+                        eprintln!("attachment {attachment_hash:?} couldn't be fetched: {err}");
+                        state
+                            .attachments
+                            .insert(attachment_hash, AttachmentView::Failed(err));
+                        Command::none()
+                    }
+                    Message::SaveAttachment(attachment) => Command::perform(
+                        save_attachment(state.happ.clone(), attachment),
+                        Message::AttachmentSaved,
+                    ),
+                    Message::AttachmentSaved(Ok(path)) => {
+                        if let Some(path) = path {
+                            println!("attachment saved to {path:?}");
+                            state.notify(
+                                Severity::Info,
+                                format!("file saved to {}", path.display()),
+                                None,
+                                None,
+                            );
+                        }
+                        Command::none()
                     }
-                    Message::HoloMessageCreated(Err(err)) => {
-                        state.saving_message = false;
-                        state.error_message = Some(err);
+                    Message::AttachmentSaved(Err(err)) => {
+                        state.notify(Severity::Error, "file couldn't be saved", Some(err), None);
                         Command::none()
                     }
+                    Message::CreateProfile if !state.may_create_profile() => Command::none(),
                     Message::CreateProfile => {
                         println!("creating profile for nickname {}", state.nickname);
+                        state.creating_profile = true;
                         Command::perform(
                             create_profile(state.happ.clone(), state.nickname.clone()),
                             Message::ProfileCreated,
                         )
                     }
                     Message::ProfileCreated(Ok(profile)) => {
-                        println!("profile created: {profile:?}");
-                        state.profile = Some(profile);
-                        text_input::focus::<Message>(MESSAGE_INPUT_ID.clone())
+                        println!("profile of {} created", profile.nickname);
+                        state.creating_profile = false;
+                        state.set_profile(Some(profile));
+                        // a new agent has no read markers yet
+                        state.read_markers_loaded = true;
+                        Command::batch(vec![
+                            text_input::focus::<Message>(MESSAGE_INPUT_ID.clone()),
+                            state.announce_presence(),
+                        ])
                     }
                     Message::ProfileCreated(Err(err)) => {
-                        eprintln!("error creating profile: {err}");
-                        state.error_message = Some(format!("profile couldn't be created: {err}"));
+                        state.creating_profile = false;
+                        state.notify(
+                            Severity::Error,
+                            "profile couldn't be created",
+                            Some(err),
+                            Some(Message::CreateProfile),
+                        );
                         Command::none()
                     }
-                    Message::HoloMessagesFetched(Ok(holo_messages)) => {
+                    Message::HoloMessagesFetched(Ok(page)) => {
                         state.loading_messages = false;
-                        state.holo_messages = holo_messages;
+                        let newest = |messages: &[FeedMessage]| {
+                            messages
+                                .first()
+                                .map(|holo_mess| holo_mess.action_hash.clone())
+                        };
+                        let has_new_messages =
+                            newest(&page.messages) != newest(&state.holo_messages);
+                        state.merge_latest_page(page);
+                        let mut commands = vec![
+                            state.mark_viewed(),
+                            state.fetch_new_attachments(),
+                            state.fetch_author_profiles(),
+                        ];
+                        if has_new_messages && state.feed_at_bottom {
+                            commands.push(state.scroll_feed_to_bottom());
+                        }
+                        Command::batch(commands)
+                    }
+                    Message::OlderMessagesFetched(Ok(page)) => {
+                        state.loading_older_messages = false;
+                        state.older_messages = page.older;
+                        let height_before: f32 = feed_rows(state)
+                            .iter()
+                            .map(|row| estimated_height(state, row))
+                            .sum();
+                        for holo_mess in page.messages {
+                            if !state
+                                .holo_messages
+                                .iter()
+                                .any(|loaded| loaded.action_hash == holo_mess.action_hash)
+                            {
+                                state.holo_messages.push(holo_mess);
+                            }
+                        }
+                        let height_after: f32 = feed_rows(state)
+                            .iter()
+                            .map(|row| estimated_height(state, row))
+                            .sum();
+                        let scroll = match state.scroll_target.clone() {
+                            Some(action_hash) => state.scroll_to_message(&action_hash),
+                            // the messages in view stay where they are
+                            None => scrollable::scroll_to(
+                                FEED_SCROLLABLE_ID.clone(),
+                                scrollable::AbsoluteOffset {
+                                    x: 0.0,
+                                    y: state.feed_offset + height_after - height_before,
+                                },
+                            ),
+                        };
+                        Command::batch(vec![
+                            scroll,
+                            state.fetch_new_attachments(),
+                            state.fetch_author_profiles(),
+                        ])
+                    }
+                    Message::OlderMessagesFetched(Err(err)) => {
+                        state.loading_older_messages = false;
+                        state.scroll_target = None;
+                        state.notify(
+                            Severity::Warning,
+                            "older messages couldn't be fetched",
+                            Some(err),
+                            Some(Message::LoadOlderMessages),
+                        );
+                        Command::none()
+                    }
+                    Message::LoadOlderMessages => state.load_older_messages(),
+                    Message::FeedScrolled(viewport) => {
+                        state.feed_offset = viewport.absolute_offset().y;
+                        state.feed_at_bottom = viewport.relative_offset().y >= 0.99;
+                        if state.feed_offset < LOAD_OLDER_THRESHOLD {
+                            state.load_older_messages()
+                        } else {
+                            Command::none()
+                        }
+                    }
+                    Message::WindowResized(height) => {
+                        state.window_height = height as f32;
                         Command::none()
                     }
                     Message::HoloMessagesFetched(Err(err)) => {
                         state.loading_messages = false;
-                        state.error_message =
-                            Some(format!("holo_messages couldn't be fetched: {err}"));
+                        state.notify(
+                            Severity::Warning,
+                            "messages couldn't be fetched",
+                            Some(err),
+                            None,
+                        );
+                        Command::none()
+                    }
+                    Message::ShowScreen(screen) => {
+                        state.screen = screen;
+                        state.highlighted_message = None;
+                        state.scroll_target = None;
+                        match screen {
+                            Screen::Feed => {
+                                state.new_messages_since = state.last_seen_at(FEED_CHANNEL);
+                                // the feed is laid out anew, starting at the newest messages
+                                Command::batch(vec![
+                                    text_input::focus::<Message>(MESSAGE_INPUT_ID.clone()),
+                                    state.mark_viewed(),
+                                    state.scroll_feed_to_bottom(),
+                                ])
+                            }
+                            Screen::DirectMessages => Command::perform(
+                                fetch_direct_messages(state.happ.clone()),
+                                Message::DirectMessagesFetched,
+                            ),
+                            Screen::Mentions => Command::perform(
+                                fetch_mentions(state.happ.clone()),
+                                Message::MentionsFetched,
+                            ),
+                            Screen::Search => text_input::focus::<Message>(SEARCH_INPUT_ID.clone()),
+                            Screen::Members => Command::batch(vec![
+                                text_input::focus::<Message>(MEMBER_SEARCH_INPUT_ID.clone()),
+                                state.search_members(),
+                            ]),
+                            Screen::Profile => {
+                                state.reset_profile_edits();
+                                Command::none()
+                            }
+                            Screen::Settings => Command::none(),
+                        }
+                    }
+                    Message::ThemeChanged(choice) => {
+                        state.settings.theme = match choice {
+                            ThemeChoice::Light => ThemeSetting::Light,
+                            ThemeChoice::Dark => ThemeSetting::Dark,
+                            // picking it again keeps the edited colors
+                            ThemeChoice::Custom => match &state.settings.theme {
+                                ThemeSetting::Custom(palette) => {
+                                    ThemeSetting::Custom(palette.clone())
+                                }
+                                _ => ThemeSetting::Custom(CustomPalette::default()),
+                            },
+                        };
+                        state.save_settings()
+                    }
+                    Message::CustomColorChanged(color, value) => {
+                        if let ThemeSetting::Custom(palette) = &mut state.settings.theme {
+                            *color.of_mut(palette) = value;
+                        }
+                        state.save_settings()
+                    }
+                    Message::FontScaleChanged(font_scale) => {
+                        state.settings.font_scale = font_scale;
+                        state.save_settings()
+                    }
+                    Message::TimestampFormatChanged(timestamp_format) => {
+                        state.settings.timestamp_format = timestamp_format;
+                        state.save_settings()
+                    }
+                    Message::EnterToSendToggled(enter_to_send) => {
+                        state.settings.enter_to_send = enter_to_send;
+                        state.save_settings()
+                    }
+                    Message::UnreadCountInTitleToggled(unread_count_in_title) => {
+                        state.settings.notifications.unread_count_in_title = unread_count_in_title;
+                        state.save_settings()
+                    }
+                    Message::TypingIndicatorsToggled(typing_indicators) => {
+                        state.settings.notifications.typing_indicators = typing_indicators;
+                        state.save_settings()
+                    }
+                    Message::SettingsSaved(Ok(())) => Command::none(),
+                    Message::SettingsSaved(Err(err)) => {
+                        state.notify(
+                            Severity::Error,
+                            "settings couldn't be saved",
+                            Some(err),
+                            None,
+                        );
+                        Command::none()
+                    }
+                    Message::EditedNicknameChanged(nickname) => {
+                        state.edited_nickname = nickname;
+                        Command::none()
+                    }
+                    Message::EditedBioChanged(bio) => {
+                        state.edited_bio = bio;
+                        Command::none()
+                    }
+                    Message::EditedColorChanged(color) => {
+                        state.edited_color = color;
+                        Command::none()
+                    }
+                    Message::PickAvatar => Command::perform(pick_image(), Message::AvatarPicked),
+                    Message::AvatarPicked(Some(path)) => {
+                        state.loading_avatar = true;
+                        Command::perform(load_avatar(path), Message::AvatarLoaded)
+                    }
+                    Message::AvatarPicked(None) => Command::none(),
+                    Message::AvatarLoaded(Ok(avatar)) => {
+                        state.loading_avatar = false;
+                        state.edited_avatar = Some(avatar);
+                        Command::none()
+                    }
+                    Message::AvatarLoaded(Err(err)) => {
+                        state.loading_avatar = false;
+                        state.notify(
+                            Severity::Error,
+                            "avatar couldn't be loaded",
+                            Some(err),
+                            None,
+                        );
+                        Command::none()
+                    }
+                    Message::RemoveAvatar => {
+                        state.edited_avatar = None;
+                        Command::none()
+                    }
+                    Message::SaveProfile => {
+                        state.saving_profile = true;
+                        let details = ProfileDetails {
+                            bio: state.edited_bio.clone(),
+                            color: parse_color(&state.edited_color),
+                            avatar: state.edited_avatar.clone(),
+                        };
+                        Command::perform(
+                            update_profile(
+                                state.happ.clone(),
+                                state.edited_nickname.trim().to_string(),
+                                details,
+                            ),
+                            Message::ProfileUpdated,
+                        )
+                    }
+                    Message::ProfileUpdated(Ok(profile)) => {
+                        println!("profile of {} updated", profile.nickname);
+                        state.saving_profile = false;
+                        state.set_profile(Some(profile));
+                        state.reset_profile_edits();
+                        let my_agent_key = state.my_agent_key().clone();
+                        state.authors.remove(&my_agent_key);
+                        state.fetch_author_profiles()
+                    }
+                    Message::AuthorProfilesFetched(Ok(profiles)) => {
+                        state.insert_authors(profiles);
+                        Command::none()
+                    }
+                    Message::AuthorProfilesFetched(Err(err)) => {
+                        eprintln!("author profiles couldn't be fetched: {err}");
+                        Command::none()
+                    }
+                    Message::ProfileUpdated(Err(err)) => {
+                        state.saving_profile = false;
+                        state.notify(
+                            Severity::Error,
+                            "profile couldn't be updated",
+                            Some(err),
+                            Some(Message::SaveProfile),
+                        );
+                        Command::none()
+                    }
+                    Message::MentionsFetched(Ok(mentions)) => {
+                        state.mentions = mentions;
+                        state.fetch_author_profiles()
+                    }
+                    Message::MentionsFetched(Err(err)) => {
+                        state.notify(
+                            Severity::Warning,
+                            "mentions couldn't be fetched",
+                            Some(err),
+                            None,
+                        );
+                        Command::none()
+                    }
+                    Message::DirectMessagesFetched(Ok(direct_messages)) => {
+                        state.direct_messages = direct_messages;
+                        state.mark_viewed()
+                    }
+                    Message::DirectMessagesFetched(Err(err)) => {
+                        state.notify(
+                            Severity::Warning,
+                            "direct messages couldn't be fetched",
+                            Some(err),
+                            None,
+                        );
+                        Command::none()
+                    }
+                    Message::SelectConversation(peer) => {
+                        state.selected_peer = Some(peer);
+                        Command::batch(vec![
+                            text_input::focus::<Message>(DIRECT_MESSAGE_INPUT_ID.clone()),
+                            state.mark_viewed(),
+                        ])
+                    }
+                    Message::NewPeerChanged(new_peer) => {
+                        state.new_peer = new_peer;
+                        Command::none()
+                    }
+                    Message::StartConversation => {
+                        match AgentPubKeyB64::from_b64_str(state.new_peer.trim()) {
+                            Ok(peer) => {
+                                state.new_peer = String::new();
+                                state.selected_peer = Some(peer.into());
+                                text_input::focus::<Message>(DIRECT_MESSAGE_INPUT_ID.clone())
+                            }
+                            Err(err) => {
+                                state.notify(
+                                    Severity::Warning,
+                                    "invalid agent key",
+                                    Some(err),
+                                    None,
+                                );
+                                Command::none()
+                            }
+                        }
+                    }
+                    Message::DirectMessageChanged(message) => {
+                        state.current_direct_message = message;
+                        Command::none()
+                    }
+                    Message::EncryptDirectMessageToggled(encrypt) => {
+                        state.encrypt_direct_message = encrypt;
+                        Command::none()
+                    }
+                    Message::SendDirectMessage => {
+                        if let Some(peer) = &state.selected_peer {
+                            state.sending_direct_message = true;
+                            Command::perform(
+                                send_direct_message(
+                                    state.happ.clone(),
+                                    peer.clone(),
+                                    state.current_direct_message.clone(),
+                                    state.encrypt_direct_message,
+                                ),
+                                Message::DirectMessageSent,
+                            )
+                        } else {
+                            Command::none()
+                        }
+                    }
+                    Message::DirectMessageSent(Ok(sent)) => {
+                        state.sending_direct_message = false;
+                        state.current_direct_message = String::new();
+                        if !sent.delivered {
+                            println!("direct message {:?} not delivered", sent.action_hash);
+                            state.undelivered_messages.insert(sent.action_hash);
+                        }
+                        Command::batch(vec![
+                            text_input::focus::<Message>(DIRECT_MESSAGE_INPUT_ID.clone()),
+                            Command::perform(
+                                fetch_direct_messages(state.happ.clone()),
+                                Message::DirectMessagesFetched,
+                            ),
+                        ])
+                    }
+                    Message::DirectMessageSent(Err(err)) => {
+                        state.sending_direct_message = false;
+                        state.notify(
+                            Severity::Error,
+                            "direct message couldn't be sent",
+                            Some(err),
+                            Some(Message::SendDirectMessage),
+                        );
+                        Command::none()
+                    }
+                    Message::ReadMarkersFetched(Ok(read_markers)) => {
+                        for read_marker in read_markers {
+                            if state.last_seen_at(&read_marker.channel)
+                                < Some(read_marker.last_seen_at)
+                            {
+                                state
+                                    .read_markers
+                                    .insert(read_marker.channel.clone(), read_marker);
+                            }
+                        }
+                        state.read_markers_loaded = true;
+                        state.new_messages_since = state.last_seen_at(FEED_CHANNEL);
+                        Command::perform(
+                            fetch_messages_page(state.happ.clone(), None),
+                            Message::HoloMessagesFetched,
+                        )
+                    }
+                    Message::ReadMarkersFetched(Err(err)) => {
+                        state.loading_messages = false;
+                        state.notify(
+                            Severity::Warning,
+                            "read markers couldn't be fetched",
+                            Some(err),
+                            None,
+                        );
+                        Command::none()
+                    }
+                    Message::ReadMarkerSet(Ok(_)) => Command::none(),
+                    Message::ReadMarkerSet(Err(err)) => {
+                        eprintln!("read marker couldn't be set: {err}");
+                        Command::none()
+                    }
+                    Message::TypingNotified(Ok(())) => Command::none(),
+                    Message::TypingNotified(Err(err)) => {
+                        eprintln!("typing notification couldn't be sent: {err}");
+                        Command::none()
+                    }
+                    Message::SignalReceived(HoloMessageSignal::Typing { agent, nickname }) => {
+                        state.typing.insert(agent, (nickname, Instant::now()));
+                        Command::none()
+                    }
+                    Message::SignalReceived(HoloMessageSignal::ProfileUpdated { agent }) => {
+                        state.authors.remove(&agent);
+                        state.fetch_author_profiles()
+                    }
+                    Message::Tick(now) => {
+                        state.typing.retain(|_, (_, last_seen)| {
+                            now.duration_since(*last_seen) < TYPING_INDICATOR_TIMEOUT
+                        });
+                        state.notifications.expire(now);
+                        Command::none()
+                    }
+                    Message::DismissNotification(id) => {
+                        state.notifications.dismiss(id);
+                        Command::none()
+                    }
+                    Message::RetryNotification(id) => match state.notifications.take_retry(id) {
+                        Some(retry) => Command::perform(async { retry }, |retry| retry),
+                        None => Command::none(),
+                    },
+                    Message::ToggleNotificationDetails(id) => {
+                        state.notifications.toggle_details(id);
+                        Command::none()
+                    }
+                    Message::DnaPropertiesFetched(Ok(dna_properties)) => {
+                        state.dna_properties = Some(dna_properties);
+                        Command::none()
+                    }
+                    Message::DnaPropertiesFetched(Err(err)) => {
+                        state.notify(
+                            Severity::Warning,
+                            "DNA properties couldn't be fetched",
+                            Some(err),
+                            None,
+                        );
+                        Command::none()
+                    }
+                    Message::PinnedFetched(Ok(pinned)) => {
+                        state.pinned = pinned;
+                        state.fetch_author_profiles()
+                    }
+                    Message::PinnedFetched(Err(err)) => {
+                        eprintln!("pinned messages couldn't be fetched: {err}");
+                        Command::none()
+                    }
+                    Message::PinMessage(message_hash) => Command::perform(
+                        pin_message(state.happ.clone(), message_hash),
+                        Message::PinChanged,
+                    ),
+                    Message::UnpinMessage(message_hash) => Command::perform(
+                        unpin_message(state.happ.clone(), message_hash),
+                        Message::PinChanged,
+                    ),
+                    Message::PinChanged(Ok(())) => {
+                        Command::perform(fetch_pinned(state.happ.clone()), Message::PinnedFetched)
+                    }
+                    Message::PinChanged(Err(err)) => {
+                        state.notify(Severity::Error, "pin couldn't be changed", Some(err), None);
+                        Command::none()
+                    }
+                    Message::TogglePinned => {
+                        state.pinned_expanded = !state.pinned_expanded;
+                        Command::none()
+                    }
+                    Message::TogglePollComposer => {
+                        state.composing_poll = !state.composing_poll;
+                        Command::none()
+                    }
+                    Message::PollQuestionChanged(question) => {
+                        state.poll_question = question;
+                        Command::none()
+                    }
+                    Message::PollOptionChanged(index, option) => {
+                        if let Some(poll_option) = state.poll_options.get_mut(index) {
+                            *poll_option = option;
+                        }
+                        Command::none()
+                    }
+                    Message::AddPollOption => {
+                        if state.poll_options.len() < MAX_POLL_OPTIONS {
+                            state.poll_options.push(String::new());
+                        }
+                        Command::none()
+                    }
+                    Message::RemovePollOption(index) => {
+                        if index < state.poll_options.len() {
+                            state.poll_options.remove(index);
+                        }
+                        Command::none()
+                    }
+                    Message::PollDurationChanged(poll_duration) => {
+                        state.poll_duration = poll_duration;
+                        Command::none()
+                    }
+                    Message::CreatePoll => {
+                        let Some(closes_at) = state.poll_duration.closes_at() else {
+                            return Command::none();
+                        };
+                        state.creating_poll = true;
+                        let options = state
+                            .poll_options
+                            .iter()
+                            .map(|option| option.trim().to_string())
+                            .filter(|option| !option.is_empty())
+                            .collect();
+                        Command::perform(
+                            create_poll(
+                                state.happ.clone(),
+                                state.poll_question.trim().to_string(),
+                                options,
+                                closes_at,
+                            ),
+                            Message::PollCreated,
+                        )
+                    }
+                    Message::PollCreated(Ok(_)) => {
+                        state.creating_poll = false;
+                        state.composing_poll = false;
+                        state.poll_question = String::new();
+                        state.poll_options = vec![String::new(), String::new()];
+                        Command::perform(fetch_polls(state.happ.clone()), Message::PollsFetched)
+                    }
+                    Message::PollCreated(Err(err)) => {
+                        state.creating_poll = false;
+                        state.notify(
+                            Severity::Error,
+                            "poll couldn't be created",
+                            Some(err),
+                            Some(Message::CreatePoll),
+                        );
+                        Command::none()
+                    }
+                    Message::Vote(poll_hash, option) => {
+                        state.voting.insert(poll_hash.clone());
+                        Command::perform(
+                            vote(state.happ.clone(), poll_hash.clone(), option),
+                            move |result| Message::Voted(poll_hash, result),
+                        )
+                    }
+                    Message::Voted(poll_hash, result) => {
+                        state.voting.remove(&poll_hash);
+                        if let Err(err) = result {
+                            state.notify(Severity::Error, "vote couldn't be cast", Some(err), None);
+                            Command::none()
+                        } else {
+                            Command::perform(fetch_polls(state.happ.clone()), Message::PollsFetched)
+                        }
+                    }
+                    Message::PollsFetched(Ok(polls)) => {
+                        state.polls = polls;
+                        Command::none()
+                    }
+                    Message::PollsFetched(Err(err)) => {
+                        eprintln!("polls couldn't be fetched: {err}");
+                        Command::none()
+                    }
+                    Message::ModerationFetched(Ok(moderation)) => {
+                        state.moderation = moderation;
+                        Command::none()
+                    }
+                    Message::ModerationFetched(Err(err)) => {
+                        eprintln!("moderation couldn't be fetched: {err}");
+                        Command::none()
+                    }
+                    Message::GrantModerator(agent) => Command::perform(
+                        grant_moderator(state.happ.clone(), agent),
+                        Message::ModerationChanged,
+                    ),
+                    Message::HideMessage(message_hash) => Command::perform(
+                        hide_message(state.happ.clone(), message_hash),
+                        Message::ModerationChanged,
+                    ),
+                    Message::UnhideMessage(message_hash) => Command::perform(
+                        unhide_message(state.happ.clone(), message_hash),
+                        Message::ModerationChanged,
+                    ),
+                    Message::BlockAgent(agent) => Command::perform(
+                        block_agent(state.happ.clone(), agent),
+                        Message::ModerationChanged,
+                    ),
+                    Message::UnblockAgent(agent) => Command::perform(
+                        unblock_agent(state.happ.clone(), agent),
+                        Message::ModerationChanged,
+                    ),
+                    Message::ModerationChanged(Ok(())) => Command::perform(
+                        fetch_moderation(state.happ.clone()),
+                        Message::ModerationFetched,
+                    ),
+                    Message::CreateInvite => {
+                        state.creating_invite = true;
+                        Command::perform(
+                            create_invite_code(state.happ.clone()),
+                            Message::InviteCreated,
+                        )
+                    }
+                    Message::InviteCreated(Ok(invite_code)) => {
+                        state.creating_invite = false;
+                        state.invite_code = Some(invite_code);
+                        Command::none()
+                    }
+                    Message::InviteCreated(Err(err)) => {
+                        state.creating_invite = false;
+                        state.notify(
+                            Severity::Error,
+                            "invite couldn't be created",
+                            Some(err),
+                            Some(Message::CreateInvite),
+                        );
+                        Command::none()
+                    }
+                    Message::CopyInviteCode => match &state.invite_code {
+                        Some(invite_code) => iced::clipboard::write(invite_code.clone()),
+                        None => Command::none(),
+                    },
+                    Message::ModerationChanged(Err(err)) => {
+                        state.notify(Severity::Error, "moderation failed", Some(err), None);
+                        Command::none()
+                    }
+                    Message::ShowRawMarkdownToggled(show_raw_markdown) => {
+                        state.show_raw_markdown = show_raw_markdown;
+                        Command::none()
+                    }
+                    Message::SearchTextChanged(search_text) => {
+                        state.search_text = search_text;
+                        Command::none()
+                    }
+                    Message::SearchAuthorChanged(search_author) => {
+                        state.search_author = search_author;
+                        Command::none()
+                    }
+                    Message::SearchSinceChanged(search_since) => {
+                        state.search_since = search_since;
+                        Command::none()
+                    }
+                    Message::SearchUntilChanged(search_until) => {
+                        state.search_until = search_until;
+                        Command::none()
+                    }
+                    Message::Search => state.search(),
+                    Message::SearchResultsFetched(Ok(search_results)) => {
+                        state.searching = false;
+                        state.search_results = Some(search_results);
+                        Command::none()
+                    }
+                    Message::SearchResultsFetched(Err(err)) => {
+                        state.searching = false;
+                        state.notify(
+                            Severity::Error,
+                            "search failed",
+                            Some(err),
+                            Some(Message::Search),
+                        );
+                        Command::none()
+                    }
+                    Message::MemberSearchChanged(member_search) => {
+                        state.member_search = member_search;
+                        state.search_members()
+                    }
+                    Message::MembersFetched(member_search, Ok(profiles)) => {
+                        // results of an outdated search text are dropped
+                        if member_search == state.member_search {
+                            state.loading_members = false;
+                            let mut members: Vec<(String, AgentPubKey)> = profiles
+                                .iter()
+                                .map(|(agent, profile)| {
+                                    (profile.nickname.to_lowercase(), agent.clone())
+                                })
+                                .collect();
+                            members.sort();
+                            state.members = members.into_iter().map(|(_, agent)| agent).collect();
+                            state.insert_authors(profiles);
+                        }
+                        Command::none()
+                    }
+                    Message::MembersFetched(member_search, Err(err)) => {
+                        if member_search == state.member_search {
+                            state.loading_members = false;
+                            state.notify(
+                                Severity::Warning,
+                                "members couldn't be fetched",
+                                Some(err),
+                                None,
+                            );
+                        }
+                        Command::none()
+                    }
+                    Message::MessageMember(agent) => {
+                        state.screen = Screen::DirectMessages;
+                        state.selected_peer = Some(agent);
+                        Command::batch(vec![
+                            text_input::focus::<Message>(DIRECT_MESSAGE_INPUT_ID.clone()),
+                            Command::perform(
+                                fetch_direct_messages(state.happ.clone()),
+                                Message::DirectMessagesFetched,
+                            ),
+                        ])
+                    }
+                    Message::ShowPostsOf(agent) => {
+                        state.screen = Screen::Search;
+                        state.search_text = String::new();
+                        state.search_author = AgentPubKeyB64::from(agent).to_string();
+                        state.search_since = String::new();
+                        state.search_until = String::new();
+                        state.search()
+                    }
+                    Message::AnnouncePresence => state.refresh_presence(),
+                    Message::PresenceAnnounced(Ok(())) => Command::none(),
+                    Message::PresenceAnnounced(Err(err)) => {
+                        eprintln!("presence couldn't be announced: {err}");
+                        Command::none()
+                    }
+                    Message::PresenceChanged(PresenceChange { agent, status }) => {
+                        match status {
+                            Some(status) => state.presence.insert(agent, status),
+                            None => state.presence.remove(&agent),
+                        };
                         Command::none()
                     }
+                    Message::UserActive => {
+                        state.last_activity = Instant::now();
+                        // coming back is announced right away
+                        if matches!(state.announced_presence, Some((PresenceStatus::Away, _))) {
+                            state.announce_presence()
+                        } else {
+                            Command::none()
+                        }
+                    }
+                    Message::ShowSearchHit(search_hit) => {
+                        state.highlighted_message = Some(search_hit.action_hash);
+                        match search_hit.peer {
+                            Some(peer) => {
+                                state.screen = Screen::DirectMessages;
+                                state.selected_peer = Some(peer);
+                                Command::perform(
+                                    fetch_direct_messages(state.happ.clone()),
+                                    Message::DirectMessagesFetched,
+                                )
+                            }
+                            None => {
+                                state.screen = Screen::Feed;
+                                let scroll = state.scroll_to_message(&search_hit.action_hash);
+                                Command::batch(vec![state.mark_viewed(), scroll])
+                            }
+                        }
+                    }
                     _ => Command::none(),
                 };
                 command
@@ -239,17 +2231,106 @@ impl Application for Holomess {
             if state.profile.is_some() {
                 let happ = state.happ.clone();
 
-                iced::subscription::channel(0u8, 100, |mut sender| async move {
+                let messages = iced::subscription::channel(0u8, 100, |mut sender| async move {
                     loop {
-                        let messages = fetch_messages(happ.clone()).await;
+                        let messages = fetch_messages_page(happ.clone(), None).await;
                         if let Err(err) = messages {
                             eprintln!("polling: could not fetch messages - {err}");
                         } else {
                             let _ = sender.send(Message::HoloMessagesFetched(messages)).await;
                         }
+                        let pinned = fetch_pinned(happ.clone()).await;
+                        if let Err(err) = pinned {
+                            eprintln!("polling: could not fetch pinned messages - {err}");
+                        } else {
+                            let _ = sender.send(Message::PinnedFetched(pinned)).await;
+                        }
+                        let polls = fetch_polls(happ.clone()).await;
+                        if let Err(err) = polls {
+                            eprintln!("polling: could not fetch polls - {err}");
+                        } else {
+                            let _ = sender.send(Message::PollsFetched(polls)).await;
+                        }
+                        let moderation = fetch_moderation(happ.clone()).await;
+                        if let Err(err) = moderation {
+                            eprintln!("polling: could not fetch moderation - {err}");
+                        } else {
+                            let _ = sender.send(Message::ModerationFetched(moderation)).await;
+                        }
                         tokio::time::sleep(Duration::from_secs(1)).await;
                     }
-                })
+                });
+
+                // direct messages are polled on every screen to keep unread
+                // counts up to date
+                let happ = state.happ.clone();
+                let direct_messages =
+                    iced::subscription::channel(1u8, 100, |mut sender| async move {
+                        loop {
+                            let direct_messages = fetch_direct_messages(happ.clone()).await;
+                            if let Err(err) = direct_messages {
+                                eprintln!("polling: could not fetch direct messages - {err}");
+                            } else {
+                                let _ = sender
+                                    .send(Message::DirectMessagesFetched(direct_messages))
+                                    .await;
+                            }
+                            tokio::time::sleep(Duration::from_secs(1)).await;
+                        }
+                    });
+                let signals = iced::subscription::run_with_id(
+                    "holomessage_signals",
+                    state
+                        .happ
+                        .holomessage_signals()
+                        .map(Message::SignalReceived),
+                );
+
+                let presence_changes = iced::subscription::run_with_id(
+                    "presence_changes",
+                    state
+                        .happ
+                        .presence_changes(PRESENCE_POLL_INTERVAL)
+                        .map(Message::PresenceChanged),
+                );
+                let heartbeat =
+                    iced::time::every(PRESENCE_CHECK_INTERVAL).map(|_| Message::AnnouncePresence);
+                let events = iced::subscription::events_with(|event, _| match event {
+                    iced::Event::Keyboard(_)
+                    | iced::Event::Mouse(iced::mouse::Event::ButtonPressed(_)) => {
+                        Some(Message::UserActive)
+                    }
+                    iced::Event::Window(iced::window::Event::Resized { height, .. }) => {
+                        Some(Message::WindowResized(height))
+                    }
+                    _ => None,
+                });
+
+                let mut subscriptions = vec![
+                    messages,
+                    direct_messages,
+                    signals,
+                    presence_changes,
+                    heartbeat,
+                    events,
+                ];
+                if state.outbox.awaiting_peers().next().is_some() {
+                    subscriptions.push(
+                        iced::time::every(DELIVERY_POLL_INTERVAL).map(|_| Message::CheckDelivery),
+                    );
+                }
+                // typing indicators and notifications time out and expiry
+                // countdowns run
+                if !state.typing.is_empty()
+                    || state.has_expiring_messages()
+                    || state.notifications.has_expiring()
+                {
+                    subscriptions
+                        .push(iced::time::every(Duration::from_secs(1)).map(Message::Tick));
+                }
+                Subscription::batch(subscriptions)
+            } else if state.notifications.has_expiring() {
+                iced::time::every(Duration::from_secs(1)).map(Message::Tick)
             } else {
                 Subscription::none()
             }
@@ -260,20 +2341,71 @@ impl Application for Holomess {
 
     fn view(&self) -> iced::Element<'_, Self::Message> {
         let header = match self {
-            Holomess::Starting => column![text("Starting up HoloMessage...")],
+            Holomess::Starting(..) => column![text("Starting up HoloMessage...")],
             Holomess::Running(state) => {
                 if state.loading_profile {
                     column![text("Fetching profile...")]
                 } else {
                     if let Some(profile) = &state.profile {
-                        column![text(format!("Welcome back, {}", &profile.nickname)),]
+                        let mut invite_button = button(text("Create invite code"));
+                        if !state.creating_invite {
+                            invite_button = invite_button.on_press(Message::CreateInvite);
+                        }
+                        let mut welcome = text(format!("Welcome back, {}", &profile.nickname));
+                        if let Some(color) = state.profile_color() {
+                            welcome = welcome.style(iced::theme::Text::Color(color));
+                        }
+                        let mut greeting = row![].spacing(10).align_items(iced::Alignment::Center);
+                        if let Some(avatar) = &state.avatar {
+                            greeting = greeting.push(
+                                image(avatar.clone())
+                                    .width(HEADER_AVATAR_SIZE)
+                                    .height(HEADER_AVATAR_SIZE),
+                            );
+                        }
+                        let mut header = column![row![
+                            greeting.push(welcome),
+                            Space::with_width(Length::Fill),
+                            invite_button
+                        ]
+                        .align_items(iced::Alignment::Center)]
+                        .spacing(10);
+                        if let Some(invite_code) = &state.invite_code {
+                            header = header.push(
+                                row![
+                                    text("Invite code:"),
+                                    TextInput::new("", invite_code).padding(5),
+                                    button(text("Copy")).on_press(Message::CopyInviteCode)
+                                ]
+                                .spacing(10)
+                                .align_items(iced::Alignment::Center),
+                            );
+                        }
+                        header
                     } else {
-                        let input = TextInput::new("Enter your nickname", &state.nickname)
+                        let mut input = TextInput::new("Enter your nickname", &state.nickname)
                             .padding(10)
-                            .on_input(Message::NicknameChanged)
                             .on_submit(Message::CreateProfile)
                             .id(PROFILE_INPUT_ID.clone());
-                        column![text("First time here, let's create a profile:"), input]
+                        if !state.creating_profile {
+                            input = input.on_input(Message::NicknameChanged);
+                        }
+                        let mut create_button = button(text(if state.creating_profile {
+                            "Creating..."
+                        } else {
+                            "Create profile"
+                        }));
+                        if state.may_create_profile() {
+                            create_button = create_button.on_press(Message::CreateProfile);
+                        }
+                        column![
+                            text("First time here, let's create a profile:"),
+                            row![input, create_button]
+                                .spacing(10)
+                                .align_items(iced::Alignment::Center),
+                            nickname_feedback(state)
+                        ]
+                        .spacing(10)
                     }
                 }
             }
@@ -283,46 +2415,123 @@ impl Application for Holomess {
             ],
         };
 
-        let mut mess_input = row![];
+        let mut nav = row![].spacing(10);
         if let Holomess::Running(state) = self {
             if state.profile.is_some() {
+                nav = nav
+                    .push(nav_button(
+                        with_unread_count("Messages", state.unread_feed_count()),
+                        Screen::Feed,
+                        state.screen,
+                    ))
+                    .push(nav_button(
+                        with_unread_count("Direct messages", state.unread_direct_messages_count()),
+                        Screen::DirectMessages,
+                        state.screen,
+                    ))
+                    .push(nav_button(
+                        "Mentions".to_string(),
+                        Screen::Mentions,
+                        state.screen,
+                    ))
+                    .push(nav_button(
+                        "Search".to_string(),
+                        Screen::Search,
+                        state.screen,
+                    ))
+                    .push(nav_button(
+                        "People".to_string(),
+                        Screen::Members,
+                        state.screen,
+                    ))
+                    .push(nav_button(
+                        "Profile".to_string(),
+                        Screen::Profile,
+                        state.screen,
+                    ))
+                    .push(nav_button(
+                        "Settings".to_string(),
+                        Screen::Settings,
+                        state.screen,
+                    ));
+            }
+        }
+
+        let mut mess_input = row![].spacing(10).align_items(iced::Alignment::Center);
+        if let Holomess::Running(state) = self {
+            if state.profile.is_some() && state.screen == Screen::Feed {
                 let mut text_input = TextInput::new("Write a new message", &state.current_message)
                     .padding(10)
-                    .on_submit(Message::CreateHoloMessage)
                     .id(MESSAGE_INPUT_ID.clone());
-                if !state.saving_message {
-                    text_input = text_input.on_input(Message::HoloMessageChanged);
+                let mut text_input = text_input.on_input(Message::HoloMessageChanged);
+                if state.settings.enter_to_send {
+                    text_input = text_input.on_submit(Message::CreateHoloMessage);
+                }
+                mess_input = mess_input
+                    .push(text_input)
+                    .push(button(text("Send")).on_press(Message::CreateHoloMessage))
+                    .push(checkbox(
+                        "Encrypt",
+                        state.encrypt_message,
+                        Message::EncryptMessageToggled,
+                    ))
+                    .push(pick_list(
+                        &MessageExpiry::ALL[..],
+                        Some(state.message_expiry),
+                        Message::MessageExpiryChanged,
+                    ));
+                if state.uploading_attachment {
+                    mess_input = mess_input.push(text("Uploading..."));
+                } else if let Some((filename, _)) = &state.current_attachment {
+                    mess_input = mess_input
+                        .push(text(format!("{ATTACHMENT_ICON} {filename}")))
+                        .push(button(text("✕")).on_press(Message::RemoveAttachment));
+                } else {
+                    mess_input =
+                        mess_input.push(button(text("Attach")).on_press(Message::PickAttachment));
+                }
+                mess_input = mess_input.push(
+                    button(text(format!("{POLL_ICON} Poll"))).on_press(Message::TogglePollComposer),
+                );
+            }
+        }
+
+        let mut poll_composer = column![];
+        if let Holomess::Running(state) = self {
+            if state.profile.is_some() && state.screen == Screen::Feed && state.composing_poll {
+                poll_composer = poll_composer.push(poll_composer_view(state));
+            }
+        }
+
+        let mut typing_indicator = column![];
+        if let Holomess::Running(state) = self {
+            if state.screen == Screen::Feed && state.settings.notifications.typing_indicators {
+                if let Some(typing) = typing_text(&state.typing) {
+                    typing_indicator = typing_indicator.push(
+                        text(typing)
+                            .size(14)
+                            .style(iced::theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+                    );
                 }
-                mess_input = mess_input.push(text_input);
             }
         }
 
-        let (holo_messages, error) = match self {
+        let (holo_messages, notifications) = match self {
             Holomess::Running(state) => {
+                let notifications = notifications_view(&state.notifications);
                 if state.profile.is_some() {
-                    // Messages header
-                    let mut messages = column![text("Messages:")];
-
-                    if state.loading_messages {
-                        messages = messages.push("Loading messages...");
-                    } else {
-                        for holo_mess in state.holo_messages.iter() {
-                            let mess_row = row![text(holo_mess.text.clone())];
-                            messages = messages.push(mess_row);
-                        }
-                    }
-                    let error = if let Some(error_message) = &state.error_message {
-                        column![
-                            text("An error happened:"),
-                            text(error_message)
-                                .style(iced::theme::Text::Color(Color::from_rgb(1.0, 0.0, 0.0)))
-                        ]
-                    } else {
-                        column![]
+                    let body = match state.screen {
+                        Screen::Feed => feed_view(state),
+                        Screen::DirectMessages => direct_messages_view(state),
+                        Screen::Mentions => mentions_view(state),
+                        Screen::Search => search_view(state),
+                        Screen::Members => members_view(state),
+                        Screen::Profile => profile_view(state),
+                        Screen::Settings => settings_view(state),
                     };
-                    (messages, error)
+                    (body, notifications)
                 } else {
-                    (column![], column![])
+                    (column![], notifications)
                 }
             }
             _ => (column![], column![]),
@@ -330,10 +2539,12 @@ impl Application for Holomess {
 
         column![
             header,
-            mess_input,
+            nav,
             holo_messages,
             Space::with_height(Length::Fill),
-            error
+            // the composer goes below the messages, like in a chat
+            column![typing_indicator, poll_composer, mess_input].spacing(5),
+            notifications
         ]
         .width(Length::Fill)
         .padding(20)
@@ -342,23 +2553,1360 @@ impl Application for Holomess {
     }
 }
 
-mod holochain {
-    use crate::Happ;
-    use hc_zome_profiles_integrity::Profile;
-    use hdk::prelude::ActionHash;
-    use holomessage_integrity::HoloMessage;
-    use std::{path::PathBuf, sync::Arc};
-
-    /// Spawn a Holochain conductor, install app and connect websockets to make
-    /// requests to conductor.
-    pub(crate) async fn start_happ(path: PathBuf) -> Result<Happ, String> {
-        Happ::start_holochain_app(path).await
+/// Toasts with their details on demand and buttons to retry and dismiss.
+fn notifications_view(notifications: &Notifications<Message>) -> Column<'_, Message> {
+    let mut toasts = column![].spacing(5);
+    for notification in notifications.iter() {
+        let mut summary = notification.summary.clone();
+        if notification.count > 1 {
+            summary = format!("{summary} ({}×)", notification.count);
+        }
+        let mut toast_row = row![text(summary), Space::with_width(Length::Fill)]
+            .spacing(10)
+            .align_items(iced::Alignment::Center);
+        if notification.details.is_some() {
+            toast_row = toast_row.push(
+                button(text(if notification.show_details {
+                    "Hide details"
+                } else {
+                    "Details"
+                }))
+                .style(iced::theme::Button::Text)
+                .on_press(Message::ToggleNotificationDetails(notification.id)),
+            );
+        }
+        if notification.retry.is_some() {
+            toast_row = toast_row
+                .push(button(text("Retry")).on_press(Message::RetryNotification(notification.id)));
+        }
+        toast_row = toast_row.push(
+            button(text("✕"))
+                .style(iced::theme::Button::Text)
+                .on_press(Message::DismissNotification(notification.id)),
+        );
+        let mut toast = column![toast_row].spacing(5);
+        if let (true, Some(details)) = (notification.show_details, &notification.details) {
+            toast = toast.push(text(details).size(14));
+        }
+        toasts = toasts.push(container(toast).padding(8).width(Length::Fill).style(
+            iced::theme::Container::Custom(Box::new(Toast(notification.severity))),
+        ));
     }
+    toasts
+}
 
-    pub(crate) async fn fetch_profile(happ: Arc<Happ>) -> Result<Option<Profile>, String> {
-        happ.fetch_profile(happ.cell_id.agent_pubkey().clone())
-            .await
-    }
+/// Background of a toast, by severity.
+struct Toast(Severity);
+
+impl container::StyleSheet for Toast {
+    type Style = Theme;
+
+    fn appearance(&self, theme: &Theme) -> container::Appearance {
+        let palette = theme.extended_palette();
+        let pair = match self.0 {
+            Severity::Info => palette.background.strong,
+            Severity::Warning => palette.primary.weak,
+            Severity::Error => palette.danger.base,
+        };
+        container::Appearance {
+            text_color: Some(pair.text),
+            background: Some(pair.color.into()),
+            border_radius: 5.0.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Validation message and alternatives for the nickname of a new profile.
+fn nickname_feedback(state: &State) -> Element<'_, Message> {
+    match &state.nickname_problem {
+        // nothing typed yet needs no hint
+        Some(Some(NicknameProblem::TooShort)) if state.nickname.is_empty() => column![].into(),
+        Some(Some(problem)) => {
+            let mut feedback = column![text(problem.to_string())
+                .size(14)
+                .style(iced::theme::Text::Color(Color::from_rgb(1.0, 0.0, 0.0)))]
+            .spacing(5);
+            if let NicknameProblem::Taken { suggestions } = problem {
+                let mut alternatives = row![text("Available:").size(14)]
+                    .spacing(5)
+                    .align_items(iced::Alignment::Center);
+                for suggestion in suggestions {
+                    alternatives = alternatives.push(
+                        button(text(suggestion).size(14))
+                            .style(iced::theme::Button::Text)
+                            .on_press(Message::NicknameChanged(suggestion.clone())),
+                    );
+                }
+                feedback = feedback.push(alternatives);
+            }
+            feedback.into()
+        }
+        Some(None) => column![].into(),
+        None => text("Checking nickname...")
+            .size(14)
+            .style(iced::theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5)))
+            .into(),
+    }
+}
+
+fn nav_button<'a>(label: String, screen: Screen, current: Screen) -> Element<'a, Message> {
+    let mut nav_button = button(text(label));
+    if screen != current {
+        nav_button = nav_button.on_press(Message::ShowScreen(screen));
+    }
+    nav_button.into()
+}
+
+fn with_unread_count(label: &str, unread: usize) -> String {
+    if unread > 0 {
+        format!("{label} ({unread})")
+    } else {
+        label.to_string()
+    }
+}
+
+/// "alice is typing..." for all agents with an active typing indicator.
+fn typing_text(typing: &HashMap<AgentPubKey, (String, Instant)>) -> Option<String> {
+    let mut nicknames: Vec<&str> = typing
+        .values()
+        .map(|(nickname, _)| nickname.as_str())
+        .collect();
+    nicknames.sort();
+    match nicknames.as_slice() {
+        [] => None,
+        [nickname] => Some(format!("{nickname} is typing...")),
+        [rest @ .., last] => Some(format!("{} and {last} are typing...", rest.join(", "))),
+    }
+}
+
+fn day_separator<'a>(day: chrono::NaiveDate) -> Element<'a, Message> {
+    row![
+        horizontal_rule(1),
+        text(format_day(day, day_of(Timestamp::now())))
+            .size(14)
+            .style(iced::theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+        horizontal_rule(1)
+    ]
+    .spacing(10)
+    .align_items(iced::Alignment::Center)
+    .into()
+}
+
+/// Messages with a separator wherever the day changes.
+fn message_list<'a>(
+    state: &'a State,
+    messages: &'a [FeedMessage],
+    mut list: Column<'a, Message>,
+) -> Column<'a, Message> {
+    let mut previous_day = None;
+    for holo_mess in messages.iter().filter(|m| state.is_visible(m)) {
+        let day = day_of(holo_mess.timestamp);
+        if day != previous_day {
+            if let Some(day) = day {
+                list = list.push(day_separator(day));
+            }
+            previous_day = day;
+        }
+        list = list.push(feed_message_view(state, holo_mess, true));
+    }
+    list
+}
+
+fn new_messages_divider<'a>() -> Element<'a, Message> {
+    row![
+        horizontal_rule(1),
+        text("new messages").style(iced::theme::Text::Color(Color::from_rgb(1.0, 0.0, 0.0))),
+        horizontal_rule(1)
+    ]
+    .spacing(10)
+    .align_items(iced::Alignment::Center)
+    .into()
+}
+
+/// Abbreviated agent key, for display only.
+fn short_key(agent: &AgentPubKey) -> String {
+    let key = AgentPubKeyB64::from(agent.clone()).to_string();
+    format!("…{}", &key[key.len() - 8..])
+}
+
+fn feed_view(state: &State) -> Column<'_, Message> {
+    let mut messages = column![row![
+        Space::with_width(Length::Fill),
+        checkbox(
+            "Raw",
+            state.show_raw_markdown,
+            Message::ShowRawMarkdownToggled
+        )
+    ]];
+    if !state.pinned.is_empty() {
+        messages = messages.push(pinned_view(state));
+    }
+
+    if state.loading_messages {
+        return messages.push("Loading messages...");
+    }
+
+    // only rows near the visible part of the feed are laid out, the others
+    // are stood in for by space of their estimated height
+    let visible_from = state.feed_offset - FEED_OVERSCAN;
+    let visible_to = state.feed_offset + state.window_height + FEED_OVERSCAN;
+    let mut top = 0.0;
+    let mut space_above = 0.0;
+    let mut space_below = 0.0;
+    let mut rows = column![];
+    for row in feed_rows(state) {
+        let height = estimated_height(state, &row);
+        if top + height < visible_from {
+            space_above += height;
+        } else if top > visible_to {
+            space_below += height;
+        } else {
+            rows = rows.push(feed_row_view(state, row));
+        }
+        top += height;
+    }
+    messages.push(
+        scrollable(column![
+            Space::with_height(space_above),
+            rows,
+            Space::with_height(space_below)
+        ])
+        .height(Length::Fill)
+        .id(FEED_SCROLLABLE_ID.clone())
+        .on_scroll(Message::FeedScrolled),
+    )
+}
+
+/// A row of the feed.
+enum FeedRow<'a> {
+    OlderMessages,
+    /// A message and whether it starts a group of messages by its author.
+    Message(&'a FeedMessage, bool),
+    Poll(&'a PollResults),
+    NewMessagesDivider,
+    DaySeparator(chrono::NaiveDate),
+    /// A message of this agent that the feed doesn't include yet.
+    Outgoing(&'a OutgoingMessage),
+}
+
+/// Rows of the loaded part of the feed, oldest first.
+fn feed_rows(state: &State) -> Vec<FeedRow<'_>> {
+    let mut rows = Vec::new();
+    if state.older_messages.is_some() {
+        rows.push(FeedRow::OlderMessages);
+    }
+    let my_agent_key = state.my_agent_key();
+    let is_new = |holo_mess: &FeedMessage| Some(holo_mess.timestamp) > state.new_messages_since;
+    let has_new_messages = state
+        .holo_messages
+        .iter()
+        .any(|holo_mess| is_new(holo_mess) && &holo_mess.author != my_agent_key);
+    let mut divider_shown = false;
+    // polls go in between the messages, as far back as messages are loaded
+    let loaded_since = state
+        .older_messages
+        .as_ref()
+        .and(state.holo_messages.last())
+        .map(|oldest| oldest.timestamp);
+    let mut polls = state
+        .polls
+        .iter()
+        .rev()
+        .filter(|poll| Some(poll.created_at) >= loaded_since)
+        .peekable();
+    // the message above, to group messages of the same author
+    let mut previous: Option<&FeedMessage> = None;
+    let mut previous_day = None;
+    let mut push_day_separator = |rows: &mut Vec<FeedRow>, timestamp: Timestamp| {
+        let day = day_of(timestamp);
+        let is_new_day = day != previous_day;
+        if is_new_day {
+            rows.extend(day.map(FeedRow::DaySeparator));
+            previous_day = day;
+        }
+        is_new_day
+    };
+    for holo_mess in state
+        .holo_messages
+        .iter()
+        .rev()
+        .filter(|holo_mess| state.is_visible(holo_mess))
+    {
+        while let Some(poll) = polls.next_if(|poll| poll.created_at < holo_mess.timestamp) {
+            push_day_separator(&mut rows, poll.created_at);
+            rows.push(FeedRow::Poll(poll));
+            previous = None;
+        }
+        if push_day_separator(&mut rows, holo_mess.timestamp) {
+            previous = None;
+        }
+        if has_new_messages && !divider_shown && is_new(holo_mess) {
+            rows.push(FeedRow::NewMessagesDivider);
+            divider_shown = true;
+            previous = None;
+        }
+        let show_author = previous.map_or(true, |previous| {
+            previous.author != holo_mess.author
+                || holo_mess.timestamp.as_micros() - previous.timestamp.as_micros()
+                    > MESSAGE_GROUP_WINDOW.as_micros() as i64
+        });
+        rows.push(FeedRow::Message(holo_mess, show_author));
+        previous = Some(holo_mess);
+    }
+    for poll in polls {
+        push_day_separator(&mut rows, poll.created_at);
+        rows.push(FeedRow::Poll(poll));
+    }
+    // sent messages the loaded feed doesn't include yet
+    let in_feed = |action_hash: &ActionHash| {
+        state
+            .holo_messages
+            .iter()
+            .any(|holo_mess| &holo_mess.action_hash == action_hash)
+    };
+    for outgoing in state
+        .outbox
+        .iter()
+        .filter(|outgoing| !outgoing.status.action_hash().map_or(false, in_feed))
+    {
+        push_day_separator(&mut rows, outgoing.sent_at);
+        rows.push(FeedRow::Outgoing(outgoing));
+    }
+    rows
+}
+
+/// Rough height of a feed row, to leave space for rows that aren't laid out.
+fn estimated_height(state: &State, row: &FeedRow) -> f32 {
+    match row {
+        FeedRow::OlderMessages => 40.0,
+        FeedRow::NewMessagesDivider | FeedRow::DaySeparator(_) => 30.0,
+        FeedRow::Poll(poll) => 70.0 + 30.0 * poll.poll.options.len() as f32,
+        FeedRow::Message(holo_mess, show_author) => {
+            let lines = holo_mess.text.as_deref().map_or(1, estimated_lines);
+            // bubble padding and the time below the text
+            let mut height = 50.0 + 20.0 * lines as f32;
+            if *show_author && &holo_mess.author != state.my_agent_key() {
+                height += AUTHOR_AVATAR_SIZE + 2.0;
+            }
+            height += match holo_mess
+                .attachment
+                .as_ref()
+                .and_then(|attachment_hash| state.attachments.get(attachment_hash))
+            {
+                Some(AttachmentView::Loaded { image: Some(_), .. }) => INLINE_IMAGE_WIDTH * 0.75,
+                Some(_) => 30.0,
+                None => 0.0,
+            };
+            height
+        }
+        FeedRow::Outgoing(outgoing) => {
+            let mut height = 50.0 + 20.0 * estimated_lines(&outgoing.text) as f32;
+            if outgoing.attachment.is_some() {
+                height += 30.0;
+            }
+            if matches!(outgoing.status, DeliveryStatus::Failed(_)) {
+                // the error and the buttons to retry or discard
+                height += 50.0;
+            }
+            height
+        }
+    }
+}
+
+/// Lines a message text wraps into, roughly.
+fn estimated_lines(text: &str) -> usize {
+    text.lines().map(|line| line.len() / 80 + 1).sum()
+}
+
+fn feed_row_view<'a>(state: &'a State, row: FeedRow<'a>) -> Element<'a, Message> {
+    match row {
+        FeedRow::OlderMessages => {
+            if state.loading_older_messages {
+                text("Loading older messages...").into()
+            } else {
+                button(text("Load older messages"))
+                    .style(iced::theme::Button::Text)
+                    .on_press(Message::LoadOlderMessages)
+                    .into()
+            }
+        }
+        FeedRow::Message(holo_mess, show_author) => {
+            feed_message_view(state, holo_mess, show_author)
+        }
+        FeedRow::Outgoing(outgoing) => outgoing_message_view(state, outgoing),
+        FeedRow::Poll(poll) => poll_view(state, poll),
+        FeedRow::NewMessagesDivider => new_messages_divider(),
+        FeedRow::DaySeparator(day) => day_separator(day),
+    }
+}
+
+/// Settings of this agent's profile.
+fn profile_view(state: &State) -> Column<'_, Message> {
+    let mut avatar_row = row![].spacing(10).align_items(iced::Alignment::Center);
+    match &state.edited_avatar {
+        Some(avatar) => {
+            avatar_row = avatar_row
+                .push(
+                    image(image::Handle::from_memory(avatar.clone()))
+                        .width(PROFILE_AVATAR_SIZE)
+                        .height(PROFILE_AVATAR_SIZE),
+                )
+                .push(button(text("Remove avatar")).on_press(Message::RemoveAvatar));
+        }
+        None => avatar_row = avatar_row.push(text("No avatar")),
+    }
+    avatar_row = if state.loading_avatar {
+        avatar_row.push(text("Loading..."))
+    } else {
+        avatar_row.push(button(text("Choose avatar")).on_press(Message::PickAvatar))
+    };
+
+    let mut color_row = row![TextInput::new("#rrggbb", &state.edited_color)
+        .padding(5)
+        .width(Length::Fixed(120.0))
+        .on_input(Message::EditedColorChanged)]
+    .spacing(10)
+    .align_items(iced::Alignment::Center);
+    if let Some([r, g, b]) = parse_color(&state.edited_color) {
+        color_row = color_row.push(
+            text(&state.edited_nickname).style(iced::theme::Text::Color(Color::from_rgb8(r, g, b))),
+        );
+    } else if !state.edited_color.trim().is_empty() {
+        color_row = color_row.push(
+            text("Colors are written as #rrggbb")
+                .style(iced::theme::Text::Color(Color::from_rgb(1.0, 0.0, 0.0))),
+        );
+    }
+
+    let mut save_button = button(text(if state.saving_profile {
+        "Saving..."
+    } else {
+        "Save profile"
+    }));
+    if state.may_save_profile() {
+        save_button = save_button.on_press(Message::SaveProfile);
+    }
+
+    column![
+        text("Your profile:"),
+        avatar_row,
+        text("Nickname"),
+        TextInput::new("Nickname", &state.edited_nickname)
+            .padding(5)
+            .on_input(Message::EditedNicknameChanged),
+        text("Bio"),
+        TextInput::new("A few words about you", &state.edited_bio)
+            .padding(5)
+            .on_input(Message::EditedBioChanged),
+        text("Display color"),
+        color_row,
+        save_button
+    ]
+    .spacing(10)
+}
+
+fn settings_view(state: &State) -> Column<'_, Message> {
+    let settings = &state.settings;
+    let mut appearance = column![row![
+        text("Theme"),
+        pick_list(
+            &ThemeChoice::ALL[..],
+            Some(ThemeChoice::of(&settings.theme)),
+            Message::ThemeChanged,
+        )
+    ]
+    .spacing(10)
+    .align_items(iced::Alignment::Center)]
+    .spacing(10);
+    if let ThemeSetting::Custom(palette) = &settings.theme {
+        for color in PaletteColor::ALL {
+            let value = color.of(palette);
+            let mut color_row = row![
+                text(color.label()).width(Length::Fixed(100.0)),
+                TextInput::new("#rrggbb", value)
+                    .padding(5)
+                    .width(Length::Fixed(120.0))
+                    .on_input(move |value| Message::CustomColorChanged(color, value))
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center);
+            if parse_color(value).is_none() {
+                color_row = color_row.push(
+                    text("Colors are written as #rrggbb")
+                        .style(iced::theme::Text::Color(Color::from_rgb(1.0, 0.0, 0.0))),
+                );
+            }
+            appearance = appearance.push(color_row);
+        }
+    }
+    appearance = appearance.push(
+        row![
+            text(format!("Text size {:.0}%", settings.font_scale * 100.0)),
+            slider(
+                MIN_FONT_SCALE..=MAX_FONT_SCALE,
+                settings.font_scale,
+                Message::FontScaleChanged
+            )
+            .step(0.05)
+            .width(Length::Fixed(200.0))
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center),
+    );
+
+    column![
+        text("Appearance"),
+        appearance,
+        text("Messages"),
+        row![
+            text("Timestamps"),
+            pick_list(
+                &TimestampFormat::ALL[..],
+                Some(settings.timestamp_format),
+                Message::TimestampFormatChanged,
+            )
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center),
+        checkbox(
+            "Send with Enter",
+            settings.enter_to_send,
+            Message::EnterToSendToggled
+        ),
+        text("Notifications"),
+        checkbox(
+            "Unread count in the window title",
+            settings.notifications.unread_count_in_title,
+            Message::UnreadCountInTitleToggled
+        ),
+        checkbox(
+            "Show who is typing",
+            settings.notifications.typing_indicators,
+            Message::TypingIndicatorsToggled
+        )
+    ]
+    .spacing(10)
+}
+
+/// Palette of a custom theme. Colors that don't parse are taken from the light
+/// theme.
+fn custom_palette(palette: &CustomPalette) -> Palette {
+    let color = |value: &str, fallback: Color| {
+        parse_color(value).map_or(fallback, |[r, g, b]| Color::from_rgb8(r, g, b))
+    };
+    Palette {
+        background: color(&palette.background, Palette::LIGHT.background),
+        text: color(&palette.text, Palette::LIGHT.text),
+        primary: color(&palette.primary, Palette::LIGHT.primary),
+        success: color(&palette.success, Palette::LIGHT.success),
+        danger: color(&palette.danger, Palette::LIGHT.danger),
+    }
+}
+
+fn members_view(state: &State) -> Column<'_, Message> {
+    let search = TextInput::new("Search by nickname", &state.member_search)
+        .padding(10)
+        .on_input(Message::MemberSearchChanged)
+        .id(MEMBER_SEARCH_INPUT_ID.clone());
+
+    let mut members = column![].spacing(10);
+    if state.members.is_empty() {
+        members = members.push(text(if state.loading_members {
+            "Loading..."
+        } else {
+            "No members found."
+        }));
+    }
+    for agent in state.members.iter() {
+        if let Some(author_view) = state.authors.get(agent) {
+            members = members.push(member_card(state, agent, author_view));
+        }
+    }
+
+    column![search, members].spacing(20)
+}
+
+/// Profile card of a member with actions to reach them.
+fn member_card<'a>(
+    state: &State,
+    agent: &AgentPubKey,
+    author_view: &'a AuthorView,
+) -> Element<'a, Message> {
+    let avatar: Element<'a, Message> = match &author_view.avatar {
+        Some(avatar) => image(avatar.clone())
+            .width(MEMBER_AVATAR_SIZE)
+            .height(MEMBER_AVATAR_SIZE)
+            .into(),
+        None => Space::with_width(MEMBER_AVATAR_SIZE).into(),
+    };
+    let mut nickname = text(&author_view.profile.nickname).font(iced::Font {
+        weight: iced::font::Weight::Bold,
+        ..iced::Font::DEFAULT
+    });
+    if let Some(color) = author_view.color {
+        nickname = nickname.style(iced::theme::Text::Color(color));
+    }
+    let mut details = column![row![
+        presence_dot(state, agent),
+        nickname,
+        text(short_key(agent))
+            .size(14)
+            .style(iced::theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5)))
+    ]
+    .spacing(10)
+    .align_items(iced::Alignment::Center)]
+    .spacing(5);
+    let bio = ProfileDetails::of(&author_view.profile).bio;
+    if !bio.is_empty() {
+        details = details.push(text(bio));
+    }
+
+    let mut actions = row![].spacing(10);
+    if agent != state.my_agent_key() {
+        actions = actions
+            .push(button(text("Message directly")).on_press(Message::MessageMember(agent.clone())));
+    }
+    actions = actions
+        .push(button(text("Show their posts")).on_press(Message::ShowPostsOf(agent.clone())));
+
+    row![avatar, details.push(actions)].spacing(10).into()
+}
+
+fn poll_composer_view(state: &State) -> Element<'_, Message> {
+    let mut question = TextInput::new("Question", &state.poll_question).padding(5);
+    if !state.creating_poll {
+        question = question.on_input(Message::PollQuestionChanged);
+    }
+    let mut composer = column![question].spacing(5);
+    for (index, option) in state.poll_options.iter().enumerate() {
+        let mut option_input = TextInput::new(&format!("Option {}", index + 1), option).padding(5);
+        if !state.creating_poll {
+            option_input =
+                option_input.on_input(move |option| Message::PollOptionChanged(index, option));
+        }
+        let mut remove_button = button(text("✕"));
+        if state.poll_options.len() > 2 {
+            remove_button = remove_button.on_press(Message::RemovePollOption(index));
+        }
+        composer = composer.push(row![option_input, remove_button].spacing(5));
+    }
+    let mut add_button = button(text("Add option"));
+    if state.poll_options.len() < MAX_POLL_OPTIONS {
+        add_button = add_button.on_press(Message::AddPollOption);
+    }
+    let mut create_button = button(text("Create poll"));
+    if state.may_submit_poll() {
+        create_button = create_button.on_press(Message::CreatePoll);
+    }
+    composer = composer.push(
+        row![
+            add_button,
+            pick_list(
+                &PollDuration::ALL[..],
+                Some(state.poll_duration),
+                Message::PollDurationChanged,
+            ),
+            Space::with_width(Length::Fill),
+            button(text("Cancel")).on_press(Message::TogglePollComposer),
+            create_button
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center),
+    );
+    container(composer)
+        .padding(5)
+        .width(Length::Fill)
+        .style(iced::theme::Container::Box)
+        .into()
+}
+
+/// A poll with a result bar per option. Agents vote by clicking an option
+/// while the poll is open and they haven't voted yet.
+fn poll_view<'a>(state: &State, poll: &'a PollResults) -> Element<'a, Message> {
+    let now = Timestamp::now();
+    let is_open = poll.poll.closes_at > now;
+    let total: u32 = poll.votes.iter().sum();
+    let status = if is_open {
+        format!(
+            "{total} votes, closes in {}",
+            format_countdown(poll.poll.closes_at, now)
+        )
+    } else {
+        format!("{total} votes, closed")
+    };
+    let mut poll_view = column![
+        text(format!("{POLL_ICON} {}", poll.poll.question)),
+        text(status)
+            .size(14)
+            .style(iced::theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5)))
+    ]
+    .spacing(5);
+    let may_vote = is_open && poll.my_vote.is_none() && !state.voting.contains(&poll.poll_hash);
+    for (index, (option, votes)) in poll.poll.options.iter().zip(poll.votes.iter()).enumerate() {
+        let label = if poll.my_vote == Some(index as u32) {
+            format!("✓ {option}")
+        } else {
+            option.clone()
+        };
+        let mut option_button = button(text(label))
+            .style(iced::theme::Button::Text)
+            .width(Length::FillPortion(1));
+        if may_vote {
+            option_button =
+                option_button.on_press(Message::Vote(poll.poll_hash.clone(), index as u32));
+        }
+        poll_view = poll_view.push(
+            row![
+                option_button,
+                progress_bar(0.0..=total.max(1) as f32, *votes as f32)
+                    .width(POLL_BAR_WIDTH)
+                    .height(12),
+                text(votes)
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+        );
+    }
+    container(poll_view)
+        .padding(5)
+        .width(Length::Fill)
+        .style(iced::theme::Container::Box)
+        .into()
+}
+
+/// Inbox of messages that mention this agent.
+fn mentions_view(state: &State) -> Column<'_, Message> {
+    let mut mentions = column![text("Messages mentioning you:")];
+    if state.mentions.is_empty() {
+        mentions = mentions.push(text("Nobody has mentioned you yet."));
+    }
+    message_list(state, &state.mentions, mentions)
+}
+
+/// A message of the feed in a bubble, own messages on the right.
+/// `show_author` is unset for messages grouped below an earlier message of the
+/// same author.
+fn feed_message_view<'a>(
+    state: &'a State,
+    holo_mess: &'a FeedMessage,
+    show_author: bool,
+) -> Element<'a, Message> {
+    let mut bubble = column![message_text_row(
+        holo_mess.text.as_deref(),
+        holo_mess.encrypted,
+        state.mentions_me(holo_mess),
+        state.show_raw_markdown,
+    )]
+    .spacing(2);
+    if let Some(attachment_hash) = &holo_mess.attachment {
+        bubble = bubble.push(attachment_view(state.attachments.get(attachment_hash)));
+    }
+    let mut details = format_time_of_day(holo_mess.timestamp, state.settings.timestamp_format);
+    if let Some(expires_at) = holo_mess.expires_at {
+        details = format!(
+            "{details} · {EXPIRY_ICON} deleted in {}",
+            format_countdown(expires_at, Timestamp::now())
+        );
+    }
+    let is_mine = &holo_mess.author == state.my_agent_key();
+    // only known for messages sent in this session
+    match state.outbox.status_of(&holo_mess.action_hash) {
+        Some(DeliveryStatus::Committed(_)) => details = format!("{details} · {SENT_ICON}"),
+        Some(DeliveryStatus::Seen(_)) => details = format!("{details} · {SEEN_ICON}"),
+        _ => {}
+    }
+    bubble = bubble.push(
+        text(details)
+            .size(12)
+            .style(iced::theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+    );
+    let bubble = container(bubble)
+        .padding(8)
+        .max_width(BUBBLE_MAX_WIDTH)
+        .style(if is_mine {
+            iced::theme::Container::Custom(Box::new(OwnMessageBubble))
+        } else {
+            iced::theme::Container::Box
+        });
+
+    let mut mess_view = column![bubble].spacing(2);
+    let controls = message_controls(state, holo_mess);
+    if !controls.is_empty() {
+        mess_view = mess_view.push(Row::with_children(controls).spacing(5));
+    }
+    let mess_view: Element<'a, Message> = if is_mine {
+        row![
+            Space::with_width(Length::Fill),
+            mess_view.align_items(iced::Alignment::End)
+        ]
+        .into()
+    } else {
+        // message bodies line up next to the author's avatar
+        let mess_view = container(mess_view).padding([0, 0, 0, AUTHOR_AVATAR_SIZE as u16 + 5]);
+        if show_author {
+            column![author_header(state, &holo_mess.author), mess_view]
+                .spacing(2)
+                .into()
+        } else {
+            mess_view.into()
+        }
+    };
+    with_search_highlight(state, &holo_mess.action_hash, mess_view)
+}
+
+/// A message of this agent that is being sent, with its delivery status and
+/// what to do about a failure.
+fn outgoing_message_view<'a>(
+    state: &'a State,
+    outgoing: &'a OutgoingMessage,
+) -> Element<'a, Message> {
+    let mut bubble = column![message_text_row(
+        Some(&outgoing.text),
+        outgoing.encrypt,
+        false,
+        state.show_raw_markdown,
+    )]
+    .spacing(2);
+    if let Some((filename, _)) = &outgoing.attachment {
+        bubble = bubble.push(text(format!("{ATTACHMENT_ICON} {filename}")));
+    }
+    let time = format_time_of_day(outgoing.sent_at, state.settings.timestamp_format);
+    let status = match &outgoing.status {
+        DeliveryStatus::Pending => format!("{time} · sending..."),
+        DeliveryStatus::Committed(_) => format!("{time} · {SENT_ICON}"),
+        DeliveryStatus::Seen(_) => format!("{time} · {SEEN_ICON}"),
+        DeliveryStatus::Failed(_) => format!("{time} · not sent"),
+    };
+    bubble = bubble.push(
+        text(status)
+            .size(12)
+            .style(iced::theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+    );
+    let bubble = container(bubble)
+        .padding(8)
+        .max_width(BUBBLE_MAX_WIDTH)
+        .style(iced::theme::Container::Custom(Box::new(OwnMessageBubble)));
+
+    let mut mess_view = column![bubble].spacing(2).align_items(iced::Alignment::End);
+    if let DeliveryStatus::Failed(err) = &outgoing.status {
+        mess_view = mess_view
+            .push(
+                text(err)
+                    .size(12)
+                    .style(iced::theme::Text::Color(Color::from_rgb(1.0, 0.0, 0.0))),
+            )
+            .push(
+                row![
+                    button(text("Retry")).on_press(Message::RetryOutgoing(outgoing.id)),
+                    button(text("Discard"))
+                        .style(iced::theme::Button::Text)
+                        .on_press(Message::DiscardOutgoing(outgoing.id))
+                ]
+                .spacing(5),
+            );
+    }
+    row![Space::with_width(Length::Fill), mess_view].into()
+}
+
+/// Bubble around this agent's own messages.
+struct OwnMessageBubble;
+
+impl container::StyleSheet for OwnMessageBubble {
+    type Style = Theme;
+
+    fn appearance(&self, theme: &Theme) -> container::Appearance {
+        let primary = theme.extended_palette().primary.weak;
+        container::Appearance {
+            text_color: Some(primary.text),
+            background: Some(primary.color.into()),
+            border_radius: 10.0.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Avatar and nickname in the author's color.
+fn author_header<'a>(state: &'a State, author: &AgentPubKey) -> Element<'a, Message> {
+    let mut header = row![].spacing(5).align_items(iced::Alignment::Center);
+    let author_view = state.authors.get(author);
+    match author_view.and_then(|author_view| author_view.avatar.as_ref()) {
+        Some(avatar) => {
+            header = header.push(
+                image(avatar.clone())
+                    .width(AUTHOR_AVATAR_SIZE)
+                    .height(AUTHOR_AVATAR_SIZE),
+            )
+        }
+        None => header = header.push(Space::with_width(AUTHOR_AVATAR_SIZE)),
+    }
+    let mut nickname = match author_view {
+        Some(author_view) => text(&author_view.profile.nickname),
+        None => text(short_key(author)),
+    }
+    .font(iced::Font {
+        weight: iced::font::Weight::Bold,
+        ..iced::Font::DEFAULT
+    });
+    if let Some(color) = author_view.and_then(|author_view| author_view.color) {
+        nickname = nickname.style(iced::theme::Text::Color(color));
+    }
+    header
+        .push(presence_dot(state, author))
+        .push(nickname)
+        .into()
+}
+
+/// Dot in the color of an agent's presence: online, away or offline.
+fn presence_dot<'a>(state: &State, agent: &AgentPubKey) -> Element<'a, Message> {
+    let color = match state.presence.get(agent) {
+        Some(PresenceStatus::Online) => Color::from_rgb(0.2, 0.7, 0.3),
+        Some(PresenceStatus::Away) => Color::from_rgb(0.9, 0.6, 0.1),
+        None => Color::from_rgb(0.6, 0.6, 0.6),
+    };
+    text("●")
+        .size(12)
+        .style(iced::theme::Text::Color(color))
+        .into()
+}
+
+/// Pin and moderation controls of a message, depending on the agent's role.
+fn message_controls<'a>(state: &State, holo_mess: &FeedMessage) -> Vec<Element<'a, Message>> {
+    let control = |label: &str, message: Message| -> Element<'a, Message> {
+        button(text(label).size(14))
+            .style(iced::theme::Button::Text)
+            .on_press(message)
+            .into()
+    };
+    let action_hash = holo_mess.action_hash.clone();
+    let author = holo_mess.author.clone();
+    let mut controls = Vec::new();
+    if state.may_pin() {
+        controls.push(if state.is_pinned(&action_hash) {
+            control("Unpin", Message::UnpinMessage(action_hash.clone()))
+        } else {
+            control("Pin", Message::PinMessage(action_hash.clone()))
+        });
+    }
+    if state.is_moderator() {
+        controls.push(if state.is_hidden(holo_mess) {
+            control("Unhide", Message::UnhideMessage(action_hash))
+        } else {
+            control("Hide", Message::HideMessage(action_hash))
+        });
+        if &author != state.my_agent_key() {
+            controls.push(if state.is_blocked(&author) {
+                control("Unblock author", Message::UnblockAgent(author.clone()))
+            } else {
+                control("Block author", Message::BlockAgent(author.clone()))
+            });
+        }
+    }
+    if state.is_progenitor() && !state.moderation.moderators.contains(&author) {
+        controls.push(control("Make moderator", Message::GrantModerator(author)));
+    }
+    controls
+}
+
+/// Collapsible list of the feed's pinned messages.
+fn pinned_view(state: &State) -> Element<'_, Message> {
+    let arrow = if state.pinned_expanded { "▾" } else { "▸" };
+    let mut pinned = column![button(text(format!(
+        "{arrow} {PIN_ICON} Pinned messages ({})",
+        state.pinned.len()
+    )))
+    .style(iced::theme::Button::Text)
+    .on_press(Message::TogglePinned)]
+    .spacing(5);
+    if state.pinned_expanded {
+        pinned = message_list(state, &state.pinned, pinned);
+    }
+    container(pinned)
+        .padding(5)
+        .width(Length::Fill)
+        .style(iced::theme::Container::Box)
+        .into()
+}
+
+/// Frame the message jumped to from the search results.
+fn with_search_highlight<'a>(
+    state: &State,
+    action_hash: &ActionHash,
+    content: impl Into<Element<'a, Message>>,
+) -> Element<'a, Message> {
+    if state.highlighted_message.as_ref() == Some(action_hash) {
+        container(content)
+            .padding(5)
+            .style(iced::theme::Container::Box)
+            .into()
+    } else {
+        content.into()
+    }
+}
+
+/// Search form and results. Clicking a result jumps to the message.
+fn search_view(state: &State) -> Column<'_, Message> {
+    let form = row![
+        TextInput::new("Search messages", &state.search_text)
+            .padding(10)
+            .on_input(Message::SearchTextChanged)
+            .on_submit(Message::Search)
+            .id(SEARCH_INPUT_ID.clone())
+            .width(Length::FillPortion(3)),
+        TextInput::new("Author agent key", &state.search_author)
+            .padding(10)
+            .on_input(Message::SearchAuthorChanged)
+            .on_submit(Message::Search)
+            .width(Length::FillPortion(2)),
+        TextInput::new("From (YYYY-MM-DD)", &state.search_since)
+            .padding(10)
+            .on_input(Message::SearchSinceChanged)
+            .on_submit(Message::Search)
+            .width(Length::FillPortion(1)),
+        TextInput::new("Until (YYYY-MM-DD)", &state.search_until)
+            .padding(10)
+            .on_input(Message::SearchUntilChanged)
+            .on_submit(Message::Search)
+            .width(Length::FillPortion(1)),
+        button(text("Search")).on_press(Message::Search),
+    ]
+    .spacing(10)
+    .align_items(iced::Alignment::Center);
+
+    let mut results = column![].spacing(5);
+    match &state.search_results {
+        _ if state.searching => results = results.push(text("Searching...")),
+        None => (),
+        Some(search_results) if search_results.is_empty() => {
+            results = results.push(text("No messages found."))
+        }
+        Some(search_results) => {
+            for search_hit in search_results {
+                let location = match &search_hit.peer {
+                    Some(peer) => format!("direct message with {}", short_key(peer)),
+                    None => "messages".to_string(),
+                };
+                let hit_view = column![
+                    text(format!(
+                        "{} in {location}, {}",
+                        short_key(&search_hit.author),
+                        format_timestamp(search_hit.timestamp, state.settings.timestamp_format)
+                    ))
+                    .size(14)
+                    .style(iced::theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+                    text(&search_hit.text)
+                ];
+                results = results.push(
+                    button(hit_view)
+                        .style(iced::theme::Button::Text)
+                        .on_press(Message::ShowSearchHit(search_hit.clone())),
+                );
+            }
+        }
+    }
+
+    column![form, results].spacing(20)
+}
+
+/// Parse a search filter date, shifted by a number of days, as the start of
+/// that day in the local time zone. An empty filter is no filter.
+fn parse_search_date(date: &str, days: i64) -> Result<Option<Timestamp>, String> {
+    if date.trim().is_empty() {
+        return Ok(None);
+    }
+    let date = chrono::NaiveDate::parse_from_str(date.trim(), SEARCH_DATE_FORMAT)
+        .map_err(|err| format!("invalid date {date:?}, expected YYYY-MM-DD: {err}"))?
+        + chrono::Duration::days(days);
+    let start_of_day = date
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_local_timezone(chrono::Local)
+        .earliest()
+        .ok_or_else(|| format!("{date} doesn't start in the local time zone"))?;
+    Ok(Some(Timestamp::from_micros(
+        start_of_day.timestamp_micros(),
+    )))
+}
+
+/// A timestamp in the local time zone.
+fn local_time(timestamp: Timestamp) -> Option<chrono::DateTime<chrono::Local>> {
+    chrono::DateTime::from_timestamp_micros(timestamp.as_micros())
+        .map(|date_time| date_time.with_timezone(&chrono::Local))
+}
+
+fn format_timestamp(timestamp: Timestamp, timestamp_format: TimestampFormat) -> String {
+    local_time(timestamp)
+        .map(|date_time| {
+            date_time
+                .format(&format!("%Y-%m-%d {}", timestamp_format.time_pattern()))
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+fn day_of(timestamp: Timestamp) -> Option<chrono::NaiveDate> {
+    local_time(timestamp).map(|date_time| date_time.date_naive())
+}
+
+fn format_time_of_day(timestamp: Timestamp, timestamp_format: TimestampFormat) -> String {
+    local_time(timestamp)
+        .map(|date_time| {
+            date_time
+                .format(timestamp_format.time_pattern())
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+/// "Today", "Yesterday" or the date of `day`.
+fn format_day(day: chrono::NaiveDate, today: Option<chrono::NaiveDate>) -> String {
+    match today.map(|today| (today - day).num_days()) {
+        Some(0) => "Today".to_string(),
+        Some(1) => "Yesterday".to_string(),
+        _ => day.format("%A, %B %-d, %Y").to_string(),
+    }
+}
+
+/// Time left until `expires_at`, e. g. "1h 05m" or "4m 12s".
+fn format_countdown(expires_at: Timestamp, now: Timestamp) -> String {
+    let seconds = ((expires_at.as_micros() - now.as_micros()) / 1_000_000).max(0);
+    let (days, hours, minutes, seconds) = (
+        seconds / 86_400,
+        seconds / 3_600 % 24,
+        seconds / 60 % 60,
+        seconds % 60,
+    );
+    if days > 0 {
+        format!("{days}d {hours:02}h")
+    } else if hours > 0 {
+        format!("{hours}h {minutes:02}m")
+    } else {
+        format!("{minutes}m {seconds:02}s")
+    }
+}
+
+/// Message text rendered as markdown, or as written if `raw` is set,
+/// prefixed with a lock icon if the message was encrypted. Highlighted
+/// messages are shown in the theme's primary color.
+fn message_text_row<'a>(
+    text_content: Option<&str>,
+    encrypted: bool,
+    highlighted: bool,
+    raw: bool,
+) -> Element<'a, Message> {
+    let mut mess_row = row![].spacing(5);
+    if encrypted {
+        mess_row = mess_row.push(text(LOCK_ICON));
+    }
+    let color = highlighted.then(|| Color::from_rgb(0.9, 0.6, 0.0));
+    match text_content {
+        Some(text_content) if raw => {
+            let mut raw_text = text(text_content);
+            if let Some(color) = color {
+                raw_text = raw_text.style(iced::theme::Text::Color(color));
+            }
+            mess_row = mess_row.push(raw_text)
+        }
+        Some(text_content) => mess_row = mess_row.push(markdown::view(text_content, color)),
+        None => {
+            mess_row = mess_row.push(
+                text("encrypted for other recipients")
+                    .style(iced::theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))),
+            )
+        }
+    }
+    mess_row.into()
+}
+
+fn attachment_view(view: Option<&AttachmentView>) -> Element<'_, Message> {
+    match view {
+        None | Some(AttachmentView::Loading) => text("Loading attachment...").into(),
+        Some(AttachmentView::Failed(err)) => text(format!("Attachment unavailable: {err}"))
+            .style(iced::theme::Text::Color(Color::from_rgb(1.0, 0.0, 0.0)))
+            .into(),
+        Some(AttachmentView::Loaded { attachment, image }) => {
+            let save = button(text(format!(
+                "{ATTACHMENT_ICON} Save {} ({} KiB)",
+                attachment.filename,
+                (attachment.size + 1023) / 1024
+            )))
+            .on_press(Message::SaveAttachment(attachment.clone()));
+            match image {
+                Some(handle) => column![
+                    iced::widget::image(handle.clone()).width(Length::Fixed(INLINE_IMAGE_WIDTH)),
+                    save
+                ]
+                .spacing(5)
+                .into(),
+                None => save.into(),
+            }
+        }
+    }
+}
+
+fn direct_messages_view(state: &State) -> Column<'_, Message> {
+    let my_agent_key = AgentPubKeyB64::from(state.my_agent_key().clone());
+
+    let mut conversations = column![text("Conversations:")]
+        .spacing(5)
+        .width(Length::FillPortion(1));
+    for peer in state.conversations() {
+        let mut peer_button = button(text(with_unread_count(
+            &short_key(&peer),
+            state.unread_direct_message_count(&peer),
+        )));
+        if state.selected_peer.as_ref() != Some(&peer) {
+            peer_button = peer_button.on_press(Message::SelectConversation(peer));
+        }
+        conversations = conversations.push(peer_button);
+    }
+    conversations = conversations.push(
+        TextInput::new("Agent key to message", &state.new_peer)
+            .padding(10)
+            .on_input(Message::NewPeerChanged)
+            .on_submit(Message::StartConversation),
+    );
+
+    let mut conversation = column![].spacing(5).width(Length::FillPortion(3));
+    if let Some(peer) = &state.selected_peer {
+        conversation = conversation.push(text(format!("Conversation with {}", short_key(peer))));
+        for item in state
+            .direct_messages
+            .iter()
+            .filter(|item| item.direct_message.peer(state.my_agent_key()) == peer)
+        {
+            let sender = if &item.direct_message.from == state.my_agent_key() {
+                "you"
+            } else {
+                "them"
+            };
+            let mut mess_row = row![
+                text(format!("{sender}:")),
+                message_text_row(
+                    item.text.as_deref(),
+                    item.direct_message.content.is_encrypted(),
+                    false,
+                    state.show_raw_markdown
+                )
+            ]
+            .spacing(10);
+            if state.undelivered_messages.contains(&item.action_hash) {
+                mess_row = mess_row.push(
+                    text("not delivered")
+                        .style(iced::theme::Text::Color(Color::from_rgb(1.0, 0.0, 0.0))),
+                );
+            }
+            conversation =
+                conversation.push(with_search_highlight(state, &item.action_hash, mess_row));
+        }
+
+        let mut input = TextInput::new("Write a direct message", &state.current_direct_message)
+            .padding(10)
+            .id(DIRECT_MESSAGE_INPUT_ID.clone());
+        let mut send_button = button(text("Send"));
+        if !state.sending_direct_message {
+            input = input.on_input(Message::DirectMessageChanged);
+            if state.settings.enter_to_send {
+                input = input.on_submit(Message::SendDirectMessage);
+            }
+            send_button = send_button.on_press(Message::SendDirectMessage);
+        }
+        conversation = conversation.push(
+            row![
+                input,
+                send_button,
+                checkbox(
+                    "Encrypt",
+                    state.encrypt_direct_message,
+                    Message::EncryptDirectMessageToggled
+                )
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+        );
+    } else {
+        conversation = conversation.push(text(
+            "Select a conversation or enter an agent key to start one.",
+        ));
+    }
+
+    column![
+        text(format!("Your agent key: {my_agent_key}")),
+        row![conversations, conversation].spacing(20)
+    ]
+    .spacing(20)
+}
+
+async fn pick_image() -> Option<PathBuf> {
+    rfd::AsyncFileDialog::new()
+        .add_filter("Images", &["png", "jpg", "jpeg", "gif", "webp"])
+        .pick_file()
+        .await
+        .map(|file| file.path().to_path_buf())
+}
+
+/// Read an image file and downscale it to an avatar.
+async fn load_avatar(path: PathBuf) -> Result<Vec<u8>, String> {
+    let image_bytes = tokio::fs::read(&path)
+        .await
+        .map_err(|err| format!("could not read {:?}: {:?}", path, err))?;
+    tokio::task::spawn_blocking(move || downscale_avatar(&image_bytes))
+        .await
+        .map_err(|err| format!("downscaling avatar failed: {:?}", err))?
+}
+
+async fn save_settings(holochain_dir: PathBuf, settings: UserSettings) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || settings.save(&holochain_dir))
+        .await
+        .map_err(|err| format!("saving settings failed: {:?}", err))?
+}
+
+async fn pick_file() -> Option<PathBuf> {
+    rfd::AsyncFileDialog::new()
+        .pick_file()
+        .await
+        .map(|file| file.path().to_path_buf())
+}
+
+mod holochain {
+    use crate::{Happ, FEED_PAGE_SIZE};
+    use hc_zome_profiles_integrity::Profile;
+    use hdk::prelude::{ActionHash, AgentPubKey, EntryHash, Timestamp};
+    use holomessage_integrity::{
+        Attachment, DirectMessageSent, HoloMessageProperties, MessagesCursor, Moderation,
+        PollResults, PresenceStatus, ReadMarker,
+    };
+    use iced_holochain::{
+        happ::{DirectMessageItem, FeedMessage, FeedPage},
+        profile::{NicknameProblem, ProfileDetails},
+        search::{SearchHit, SearchQuery},
+    };
+    use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+    /// Spawn a Holochain conductor, install app and connect websockets to make
+    /// requests to conductor.
+    pub(crate) async fn start_happ(
+        path: PathBuf,
+        invite_code: Option<String>,
+    ) -> Result<Happ, String> {
+        Happ::start_holochain_app(path, invite_code).await
+    }
+
+    pub(crate) async fn create_invite_code(happ: Arc<Happ>) -> Result<String, String> {
+        happ.create_invite_code().await
+    }
+
+    pub(crate) async fn fetch_profile(happ: Arc<Happ>) -> Result<Option<Profile>, String> {
+        happ.fetch_profile(happ.cell_id.agent_pubkey().clone())
+            .await
+    }
+
+    pub(crate) async fn check_nickname(
+        happ: Arc<Happ>,
+        nickname: String,
+    ) -> Result<Option<NicknameProblem>, String> {
+        happ.check_nickname(&nickname).await
+    }
 
     pub(crate) async fn create_profile(
         happ: Arc<Happ>,
@@ -370,11 +3918,222 @@ mod holochain {
     pub(crate) async fn create_message(
         happ: Arc<Happ>,
         message: String,
+        encrypt: bool,
+        attachment: Option<EntryHash>,
+        expires_at: Option<Timestamp>,
+    ) -> Result<ActionHash, String> {
+        happ.create_message(message, encrypt, attachment, expires_at)
+            .await
+    }
+
+    pub(crate) async fn is_message_seen(
+        happ: Arc<Happ>,
+        action_hash: ActionHash,
+    ) -> Result<bool, String> {
+        happ.is_message_seen(action_hash).await
+    }
+
+    pub(crate) async fn fetch_messages_page(
+        happ: Arc<Happ>,
+        before: Option<MessagesCursor>,
+    ) -> Result<FeedPage, String> {
+        happ.fetch_messages_page(before, FEED_PAGE_SIZE).await
+    }
+
+    /// Upload a file, returning its file name and attachment hash.
+    pub(crate) async fn upload_file(
+        happ: Arc<Happ>,
+        path: PathBuf,
+    ) -> Result<(String, EntryHash), String> {
+        let filename = path
+            .file_name()
+            .map(|filename| filename.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let attachment_hash = happ.upload_file(&path).await?;
+        Ok((filename, attachment_hash))
+    }
+
+    /// Fetch an attachment, along with its content if it is an image to be
+    /// shown inline.
+    pub(crate) async fn fetch_attachment(
+        happ: Arc<Happ>,
+        attachment_hash: EntryHash,
+    ) -> Result<(Attachment, Option<Vec<u8>>), String> {
+        let attachment = happ.fetch_attachment(attachment_hash).await?;
+        let content = if attachment.is_image() {
+            Some(happ.download_file(&attachment).await?)
+        } else {
+            None
+        };
+        Ok((attachment, content))
+    }
+
+    /// Ask for a location and save an attachment there. Returns `None` if no
+    /// location was chosen.
+    pub(crate) async fn save_attachment(
+        happ: Arc<Happ>,
+        attachment: Attachment,
+    ) -> Result<Option<PathBuf>, String> {
+        let Some(file) = rfd::AsyncFileDialog::new()
+            .set_file_name(&attachment.filename)
+            .save_file()
+            .await
+        else {
+            return Ok(None);
+        };
+        let content = happ.download_file(&attachment).await?;
+        tokio::fs::write(file.path(), content)
+            .await
+            .map_err(|err| format!("could not write file: {:?}", err))?;
+        Ok(Some(file.path().to_path_buf()))
+    }
+
+    pub(crate) async fn fetch_mentions(happ: Arc<Happ>) -> Result<Vec<FeedMessage>, String> {
+        happ.fetch_mentions().await
+    }
+
+    pub(crate) async fn fetch_read_markers(happ: Arc<Happ>) -> Result<Vec<ReadMarker>, String> {
+        happ.fetch_read_markers().await
+    }
+
+    pub(crate) async fn set_read_marker(
+        happ: Arc<Happ>,
+        read_marker: ReadMarker,
     ) -> Result<ActionHash, String> {
-        happ.create_message(message).await
+        happ.set_read_marker(read_marker).await
+    }
+
+    pub(crate) async fn notify_typing(
+        happ: Arc<Happ>,
+        agents: Vec<AgentPubKey>,
+        nickname: String,
+    ) -> Result<(), String> {
+        happ.notify_typing(agents, nickname).await
+    }
+
+    pub(crate) async fn send_direct_message(
+        happ: Arc<Happ>,
+        to: AgentPubKey,
+        text: String,
+        encrypt: bool,
+    ) -> Result<DirectMessageSent, String> {
+        happ.send_direct_message(to, text, encrypt).await
+    }
+
+    pub(crate) async fn fetch_direct_messages(
+        happ: Arc<Happ>,
+    ) -> Result<Vec<DirectMessageItem>, String> {
+        happ.fetch_direct_messages().await
     }
 
-    pub(crate) async fn fetch_messages(happ: Arc<Happ>) -> Result<Vec<HoloMessage>, String> {
-        happ.fetch_messages().await
+    pub(crate) async fn pin_message(
+        happ: Arc<Happ>,
+        message_hash: ActionHash,
+    ) -> Result<(), String> {
+        happ.pin_message(message_hash).await
+    }
+
+    pub(crate) async fn unpin_message(
+        happ: Arc<Happ>,
+        message_hash: ActionHash,
+    ) -> Result<(), String> {
+        happ.unpin_message(message_hash).await
+    }
+
+    pub(crate) async fn fetch_pinned(happ: Arc<Happ>) -> Result<Vec<FeedMessage>, String> {
+        happ.fetch_pinned().await
+    }
+
+    pub(crate) async fn fetch_profiles(
+        happ: Arc<Happ>,
+        agents: Vec<AgentPubKey>,
+    ) -> Result<HashMap<AgentPubKey, Profile>, String> {
+        happ.fetch_profiles(agents).await
+    }
+
+    pub(crate) async fn announce_presence(
+        happ: Arc<Happ>,
+        status: PresenceStatus,
+    ) -> Result<(), String> {
+        happ.announce_presence(status).await
+    }
+
+    pub(crate) async fn search_members(
+        happ: Arc<Happ>,
+        nickname_prefix: String,
+    ) -> Result<HashMap<AgentPubKey, Profile>, String> {
+        happ.search_members(nickname_prefix).await
+    }
+
+    pub(crate) async fn update_profile(
+        happ: Arc<Happ>,
+        nickname: String,
+        details: ProfileDetails,
+    ) -> Result<Profile, String> {
+        happ.update_profile(nickname, details).await
+    }
+
+    pub(crate) async fn create_poll(
+        happ: Arc<Happ>,
+        question: String,
+        options: Vec<String>,
+        closes_at: Timestamp,
+    ) -> Result<ActionHash, String> {
+        happ.create_poll(question, options, closes_at).await
+    }
+
+    pub(crate) async fn vote(
+        happ: Arc<Happ>,
+        poll: ActionHash,
+        option: u32,
+    ) -> Result<ActionHash, String> {
+        happ.vote(poll, option).await
+    }
+
+    pub(crate) async fn fetch_polls(happ: Arc<Happ>) -> Result<Vec<PollResults>, String> {
+        happ.fetch_polls().await
+    }
+
+    pub(crate) async fn fetch_dna_properties(
+        happ: Arc<Happ>,
+    ) -> Result<HoloMessageProperties, String> {
+        happ.fetch_dna_properties().await
+    }
+
+    pub(crate) async fn fetch_moderation(happ: Arc<Happ>) -> Result<Moderation, String> {
+        happ.fetch_moderation().await
+    }
+
+    pub(crate) async fn grant_moderator(happ: Arc<Happ>, agent: AgentPubKey) -> Result<(), String> {
+        happ.grant_moderator(agent).await.map(|_| ())
+    }
+
+    pub(crate) async fn hide_message(
+        happ: Arc<Happ>,
+        message_hash: ActionHash,
+    ) -> Result<(), String> {
+        happ.hide_message(message_hash).await
+    }
+
+    pub(crate) async fn unhide_message(
+        happ: Arc<Happ>,
+        message_hash: ActionHash,
+    ) -> Result<(), String> {
+        happ.unhide_message(message_hash).await
+    }
+
+    pub(crate) async fn block_agent(happ: Arc<Happ>, agent: AgentPubKey) -> Result<(), String> {
+        happ.block_agent(agent).await
+    }
+
+    pub(crate) async fn unblock_agent(happ: Arc<Happ>, agent: AgentPubKey) -> Result<(), String> {
+        happ.unblock_agent(agent).await
+    }
+
+    pub(crate) async fn search_messages(
+        happ: Arc<Happ>,
+        query: SearchQuery,
+    ) -> Result<Vec<SearchHit>, String> {
+        happ.search_messages(query).await
     }
 }
@@ -0,0 +1,262 @@
+//! Rendering of CommonMark message texts with iced widgets.
+
+use holomessage_integrity::check_message_text;
+use iced::{
+    font::Weight,
+    widget::{column, container, horizontal_rule, row, text, Column, Row},
+    Color, Element, Font,
+};
+use pulldown_cmark::{Event, Parser, Tag};
+use std::iter::Peekable;
+
+const HEADING_SIZE: u16 = 22;
+const QUOTE_INDENT: u16 = 15;
+const QUOTE_COLOR: Color = Color::from_rgb(0.5, 0.5, 0.5);
+
+#[derive(Debug, PartialEq)]
+enum Block {
+    Paragraph(Vec<Span>),
+    Heading(Vec<Span>),
+    CodeBlock(String),
+    Quote(Vec<Block>),
+    List {
+        start: Option<u64>,
+        items: Vec<Vec<Block>>,
+    },
+    Rule,
+}
+
+#[derive(Debug, PartialEq)]
+struct Span {
+    text: String,
+    strong: bool,
+    emphasis: bool,
+    code: bool,
+}
+
+/// Render a message text as markdown. `color` overrides the theme's text
+/// color, e. g. for highlighted messages.
+pub fn view<'a, Message: 'a>(source: &str, color: Option<Color>) -> Element<'a, Message> {
+    match parse(source) {
+        Some(blocks) => blocks_view(&blocks, color).into(),
+        None => {
+            let mut raw_text = text(source);
+            if let Some(color) = color {
+                raw_text = raw_text.style(iced::theme::Text::Color(color));
+            }
+            raw_text.into()
+        }
+    }
+}
+
+/// The blocks of a message text, or `None` if it's longer or nests deeper
+/// than stored message texts may. Decrypted texts aren't validated by peers,
+/// and parsing and rendering recurse per nesting level.
+fn parse(source: &str) -> Option<Vec<Block>> {
+    if check_message_text(source).is_some() {
+        return None;
+    }
+    Some(parse_blocks(&mut Parser::new(source).peekable()))
+}
+
+fn parse_blocks<'s>(events: &mut Peekable<impl Iterator<Item = Event<'s>>>) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    while let Some(event) = events.peek() {
+        if is_inline(event) {
+            blocks.push(Block::Paragraph(parse_spans(events)));
+            continue;
+        }
+        match events.next().unwrap() {
+            Event::Start(Tag::Heading(..)) => blocks.push(Block::Heading(parse_spans(events))),
+            Event::Start(Tag::CodeBlock(_)) => {
+                let mut code = String::new();
+                for event in events.by_ref() {
+                    match event {
+                        Event::Text(text) => code.push_str(&text),
+                        _ => break,
+                    }
+                }
+                blocks.push(Block::CodeBlock(code.trim_end().to_string()));
+            }
+            Event::Start(Tag::BlockQuote) => blocks.push(Block::Quote(parse_blocks(events))),
+            Event::Start(Tag::List(start)) => {
+                let mut items = Vec::new();
+                while let Some(Event::Start(Tag::Item)) = events.next() {
+                    items.push(parse_blocks(events));
+                }
+                blocks.push(Block::List { start, items });
+            }
+            Event::End(Tag::BlockQuote) | Event::End(Tag::Item) => break,
+            Event::Rule => blocks.push(Block::Rule),
+            // paragraph boundaries and unsupported blocks like tables
+            _ => (),
+        }
+    }
+    blocks
+}
+
+fn is_inline(event: &Event) -> bool {
+    match event {
+        Event::Text(_) | Event::Code(_) | Event::Html(_) | Event::SoftBreak | Event::HardBreak => {
+            true
+        }
+        Event::Start(tag) | Event::End(tag) => matches!(
+            tag,
+            Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Link(..) | Tag::Image(..)
+        ),
+        _ => false,
+    }
+}
+
+/// Consume inline events up to the next block event. Links and images are
+/// shown as their text.
+fn parse_spans<'s>(events: &mut Peekable<impl Iterator<Item = Event<'s>>>) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut strong = 0;
+    let mut emphasis = 0;
+    while let Some(event) = events.next_if(is_inline) {
+        let (text, code) = match event {
+            Event::Text(text) | Event::Html(text) => (text.to_string(), false),
+            Event::Code(code) => (code.to_string(), true),
+            Event::SoftBreak => (" ".to_string(), false),
+            Event::HardBreak => ("\n".to_string(), false),
+            Event::Start(Tag::Strong) => {
+                strong += 1;
+                continue;
+            }
+            Event::End(Tag::Strong) => {
+                strong -= 1;
+                continue;
+            }
+            Event::Start(Tag::Emphasis) => {
+                emphasis += 1;
+                continue;
+            }
+            Event::End(Tag::Emphasis) => {
+                emphasis -= 1;
+                continue;
+            }
+            _ => continue,
+        };
+        spans.push(Span {
+            text,
+            strong: strong > 0,
+            emphasis: emphasis > 0,
+            code,
+        });
+    }
+    spans
+}
+
+fn blocks_view<'a, Message: 'a>(blocks: &[Block], color: Option<Color>) -> Column<'a, Message> {
+    let mut blocks_view = column![].spacing(5);
+    for block in blocks {
+        let block_view: Element<'a, Message> = match block {
+            Block::Paragraph(spans) => spans_view(spans, None, color).into(),
+            Block::Heading(spans) => spans_view(spans, Some(HEADING_SIZE), color).into(),
+            Block::CodeBlock(code) => container(text(code).font(Font::MONOSPACE))
+                .padding(5)
+                .style(iced::theme::Container::Box)
+                .into(),
+            Block::Quote(blocks) => {
+                container(blocks_view(blocks, Some(color.unwrap_or(QUOTE_COLOR))))
+                    .padding([0, 0, 0, QUOTE_INDENT])
+                    .into()
+            }
+            Block::List { start, items } => {
+                let mut list = column![].spacing(2);
+                for (i, item) in items.iter().enumerate() {
+                    let marker = match start {
+                        Some(start) => format!("{}.", start + i as u64),
+                        None => "•".to_string(),
+                    };
+                    list = list.push(row![text(marker), blocks_view(item, color)].spacing(5));
+                }
+                list.into()
+            }
+            Block::Rule => horizontal_rule(1).into(),
+        };
+        blocks_view = blocks_view.push(block_view);
+    }
+    blocks_view
+}
+
+fn spans_view<'a, Message: 'a>(
+    spans: &[Span],
+    size: Option<u16>,
+    color: Option<Color>,
+) -> Row<'a, Message> {
+    let mut spans_view = row![];
+    for span in spans {
+        let mut font = if span.code {
+            Font::MONOSPACE
+        } else {
+            Font::DEFAULT
+        };
+        if span.strong || size.is_some() {
+            font.weight = Weight::Bold;
+        }
+        // fonts have no italic style in this iced version, emphasis is set
+        // in a lighter weight instead
+        if span.emphasis {
+            font.weight = Weight::Light;
+        }
+        let mut span_view = text(&span.text).font(font);
+        if let Some(size) = size {
+            span_view = span_view.size(size);
+        }
+        if let Some(color) = color {
+            span_view = span_view.style(iced::theme::Text::Color(color));
+        }
+        spans_view = spans_view.push(span_view);
+    }
+    spans_view
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, parse_blocks, Block, Span};
+    use pulldown_cmark::Parser;
+
+    fn span(text: &str, strong: bool, emphasis: bool, code: bool) -> Span {
+        Span {
+            text: text.to_string(),
+            strong,
+            emphasis,
+            code,
+        }
+    }
+
+    #[test]
+    fn parse_inline_styles_and_blocks() {
+        let source = "**bold** *it* `code`\n\n> - one\n> - two\n\n```\nlet a = 1;\n```";
+        let blocks = parse_blocks(&mut Parser::new(source).peekable());
+        assert_eq!(
+            blocks,
+            vec![
+                Block::Paragraph(vec![
+                    span("bold", true, false, false),
+                    span(" ", false, false, false),
+                    span("it", false, true, false),
+                    span(" ", false, false, false),
+                    span("code", false, false, true),
+                ]),
+                Block::Quote(vec![Block::List {
+                    start: None,
+                    items: vec![
+                        vec![Block::Paragraph(vec![span("one", false, false, false)])],
+                        vec![Block::Paragraph(vec![span("two", false, false, false)])],
+                    ],
+                }]),
+                Block::CodeBlock("let a = 1;".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn deeply_nested_text_is_not_parsed() {
+        assert_eq!(parse(&format!("{}deep", "> ".repeat(1000))), None);
+        assert_eq!(parse(&format!("{}deep", "- ".repeat(1000))), None);
+        assert!(parse("> - quoted item").is_some());
+    }
+}
@@ -0,0 +1,145 @@
+//! Migration of feed messages out of an older version of the DNA.
+//!
+//! Every change to the integrity zome changes the DNA hash and starts a new
+//! network, so messages don't carry over by themselves. Each agent
+//! republishes their own messages in the new DNA instead, see
+//! `Happ::migrate_messages_from`. Records of the old DNA are decoded in any
+//! of the shapes `HoloMessage::try_from` accepts.
+
+use hdk::prelude::{AgentPubKey, Record, Timestamp};
+use holomessage_integrity::{check_message_expiry, CreateMessageInput, HoloMessage};
+
+/// Messages of `author` among `records`, oldest first, to be created again
+/// in the new DNA.
+///
+/// Attachments live in the old DNA and are left out. Messages that have
+/// expired, or expire too soon to be created again, are skipped.
+pub fn migrated_messages(
+    mut records: Vec<Record>,
+    author: &AgentPubKey,
+    now: Timestamp,
+) -> Vec<CreateMessageInput> {
+    records.sort_by_key(|record| record.action().timestamp());
+    records
+        .iter()
+        .filter(|record| record.action().author() == author)
+        .filter_map(|record| match HoloMessage::try_from(record) {
+            Ok(holo_message) => Some(holo_message),
+            Err(err) => {
+                eprintln!(
+                    "message {} couldn't be decoded: {:?}",
+                    record.action_address(),
+                    err
+                );
+                None
+            }
+        })
+        .filter(|holo_message| {
+            holo_message.expires_at.map_or(true, |expires_at| {
+                check_message_expiry(expires_at, now).is_none()
+            })
+        })
+        .map(|holo_message| CreateMessageInput {
+            content: holo_message.content,
+            attachment: None,
+            expires_at: holo_message.expires_at,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::migrated_messages;
+    use hdk::prelude::{
+        Action, ActionHash, ActionHashed, AgentPubKey, AppEntryBytes, AppEntryDef, Create, Entry,
+        EntryHash, EntryType, EntryVisibility, Record, SerializedBytes, Signature,
+        SignedActionHashed, Timestamp, UnsafeBytes,
+    };
+    use holomessage_integrity::{HoloMessage, MessageContent};
+
+    /// Entry bytes in the shapes written by the DNA over time.
+    const V1_TEXT: &[u8] = include_bytes!("fixtures/message_v1_text.msgpack");
+    const UNVERSIONED_CONTENT: &[u8] =
+        include_bytes!("fixtures/message_unversioned_content.msgpack");
+    const UNVERSIONED_EXPIRED: &[u8] =
+        include_bytes!("fixtures/message_unversioned_expired.msgpack");
+    const V2: &[u8] = include_bytes!("fixtures/message_v2.msgpack");
+
+    fn record(author: &AgentPubKey, timestamp: i64, entry_bytes: &[u8]) -> Record {
+        let entry = Entry::App(AppEntryBytes(SerializedBytes::from(UnsafeBytes::from(
+            entry_bytes.to_vec(),
+        ))));
+        let action = Action::Create(Create {
+            author: author.clone(),
+            timestamp: Timestamp::from_micros(timestamp),
+            action_seq: 4,
+            prev_action: ActionHash::from_raw_36(vec![0; 36]),
+            entry_type: EntryType::App(AppEntryDef::new(
+                0.into(),
+                1.into(),
+                EntryVisibility::Public,
+            )),
+            entry_hash: EntryHash::from_raw_36(vec![timestamp as u8; 36]),
+            weight: Default::default(),
+        });
+        Record::new(
+            SignedActionHashed::with_presigned(
+                ActionHashed::from_content_sync(action),
+                Signature([0; 64]),
+            ),
+            Some(entry),
+        )
+    }
+
+    fn text(text: &str) -> MessageContent {
+        MessageContent::Text(text.to_string())
+    }
+
+    #[test]
+    fn decode_all_message_shapes() {
+        let author = AgentPubKey::from_raw_36(vec![1; 36]);
+        let decoded: Vec<HoloMessage> = [V1_TEXT, UNVERSIONED_CONTENT, V2]
+            .into_iter()
+            .map(|bytes| HoloMessage::try_from(record(&author, 1, bytes)).unwrap())
+            .collect();
+        assert_eq!(
+            decoded,
+            vec![
+                HoloMessage {
+                    content: text("hello from v1"),
+                    attachment: None,
+                    expires_at: None,
+                },
+                HoloMessage {
+                    content: text("hello, unversioned"),
+                    attachment: None,
+                    expires_at: None,
+                },
+                HoloMessage {
+                    content: text("hello from v2"),
+                    attachment: None,
+                    expires_at: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn migrate_own_unexpired_messages_oldest_first() {
+        let alice = AgentPubKey::from_raw_36(vec![1; 36]);
+        let bob = AgentPubKey::from_raw_36(vec![2; 36]);
+        // newest first, as returned by `get_messages`
+        let records = vec![
+            record(&alice, 4, V2),
+            record(&alice, 3, UNVERSIONED_EXPIRED),
+            record(&bob, 2, UNVERSIONED_CONTENT),
+            record(&alice, 1, V1_TEXT),
+        ];
+        let migrated: Vec<MessageContent> =
+            migrated_messages(records, &alice, Timestamp::from_micros(10))
+                .into_iter()
+                .map(|input| input.content)
+                .collect();
+        assert_eq!(migrated, vec![text("hello from v1"), text("hello from v2")]);
+    }
+}
@@ -0,0 +1,218 @@
+//! Queue of toast notifications, generic over the message that retries a
+//! failed action.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Notifications beyond this number push out the oldest ones.
+pub const MAX_NOTIFICATIONS: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// How long a notification is shown, `None` until it is dismissed.
+    pub fn lifetime(&self) -> Option<Duration> {
+        match self {
+            Severity::Info => Some(Duration::from_secs(5)),
+            Severity::Warning => Some(Duration::from_secs(10)),
+            Severity::Error => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Notification<M> {
+    pub id: u64,
+    pub severity: Severity,
+    pub summary: String,
+    pub details: Option<String>,
+    /// Sent again to retry the action that failed.
+    pub retry: Option<M>,
+    pub show_details: bool,
+    /// How often the notification was raised while it was shown.
+    pub count: usize,
+    raised_at: Instant,
+}
+
+impl<M> Notification<M> {
+    fn is_expired(&self, now: Instant) -> bool {
+        // the details being read keep a notification open
+        !self.show_details
+            && self.severity.lifetime().map_or(false, |lifetime| {
+                now.duration_since(self.raised_at) >= lifetime
+            })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Notifications<M> {
+    queue: VecDeque<Notification<M>>,
+    next_id: u64,
+}
+
+impl<M> Default for Notifications<M> {
+    fn default() -> Self {
+        Notifications {
+            queue: VecDeque::new(),
+            next_id: 0,
+        }
+    }
+}
+
+impl<M> Notifications<M> {
+    /// Show a notification. One with the same summary and details that is
+    /// still shown is raised again instead, to not pile up failures of polls.
+    pub fn push(
+        &mut self,
+        severity: Severity,
+        summary: impl Into<String>,
+        details: Option<String>,
+        retry: Option<M>,
+        now: Instant,
+    ) -> u64 {
+        let summary = summary.into();
+        let mut count = 1;
+        let mut show_details = false;
+        if let Some(index) = self.queue.iter().position(|notification| {
+            notification.summary == summary && notification.details == details
+        }) {
+            if let Some(repeated) = self.queue.remove(index) {
+                count += repeated.count;
+                show_details = repeated.show_details;
+            }
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.queue.push_back(Notification {
+            id,
+            severity,
+            summary,
+            details,
+            retry,
+            show_details,
+            count,
+            raised_at: now,
+        });
+        while self.queue.len() > MAX_NOTIFICATIONS {
+            self.queue.pop_front();
+        }
+        id
+    }
+
+    pub fn dismiss(&mut self, id: u64) -> Option<Notification<M>> {
+        let index = self
+            .queue
+            .iter()
+            .position(|notification| notification.id == id)?;
+        self.queue.remove(index)
+    }
+
+    /// Dismiss a notification and return the message retrying its action.
+    pub fn take_retry(&mut self, id: u64) -> Option<M> {
+        self.dismiss(id).and_then(|notification| notification.retry)
+    }
+
+    pub fn toggle_details(&mut self, id: u64) {
+        if let Some(notification) = self
+            .queue
+            .iter_mut()
+            .find(|notification| notification.id == id)
+        {
+            notification.show_details = !notification.show_details;
+        }
+    }
+
+    /// Remove notifications that outlived their severity's lifetime.
+    pub fn expire(&mut self, now: Instant) {
+        self.queue
+            .retain(|notification| !notification.is_expired(now));
+    }
+
+    /// Whether any notification is dismissed automatically.
+    pub fn has_expiring(&self) -> bool {
+        self.queue
+            .iter()
+            .any(|notification| notification.severity.lifetime().is_some())
+    }
+
+    /// Notifications oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &Notification<M>> {
+        self.queue.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Notifications, Severity, MAX_NOTIFICATIONS};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn notification_queue() {
+        let start = Instant::now();
+        let mut notifications = Notifications::default();
+        let warning = notifications.push(
+            Severity::Warning,
+            "messages couldn't be fetched",
+            Some("timeout".to_string()),
+            None,
+            start,
+        );
+        let error = notifications.push(
+            Severity::Error,
+            "message couldn't be sent",
+            Some("rate limit".to_string()),
+            Some("retry"),
+            start,
+        );
+
+        // repeated failures are merged
+        notifications.push(
+            Severity::Warning,
+            "messages couldn't be fetched",
+            Some("timeout".to_string()),
+            None,
+            start,
+        );
+        let summaries: Vec<_> = notifications
+            .iter()
+            .map(|notification| (notification.summary.as_str(), notification.count))
+            .collect();
+        assert_eq!(
+            summaries,
+            vec![
+                ("message couldn't be sent", 1),
+                ("messages couldn't be fetched", 2)
+            ]
+        );
+        assert!(notifications.dismiss(warning).is_none());
+
+        // warnings expire, errors stay until dismissed
+        notifications.expire(start + Duration::from_secs(60));
+        assert_eq!(notifications.iter().count(), 1);
+        assert!(!notifications.has_expiring());
+        assert_eq!(notifications.take_retry(error), Some("retry"));
+        assert_eq!(notifications.iter().count(), 0);
+
+        // open details keep a notification
+        let info = notifications.push(Severity::Info, "file saved", None, None, start);
+        notifications.toggle_details(info);
+        notifications.expire(start + Duration::from_secs(60));
+        assert_eq!(notifications.iter().count(), 1);
+
+        for i in 0..MAX_NOTIFICATIONS {
+            notifications.push(Severity::Error, format!("error {i}"), None, None, start);
+        }
+        assert_eq!(notifications.iter().count(), MAX_NOTIFICATIONS);
+        assert_eq!(
+            notifications.iter().next().map(|n| n.summary.as_str()),
+            Some("error 0")
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! Feed messages sent by this agent that the feed doesn't include yet.
+
+use hdk::prelude::{ActionHash, EntryHash, Timestamp};
+use std::time::Duration;
+
+/// Time until the first check whether another agent got a stored message.
+/// Each further check waits twice as long as the one before.
+const FIRST_DELIVERY_CHECK: Duration = Duration::from_secs(5);
+/// Checks before a stored message is left as it is, about 20 minutes after
+/// it was sent.
+const MAX_DELIVERY_CHECKS: u32 = 8;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DeliveryStatus {
+    /// The zome call creating the message hasn't returned.
+    Pending,
+    /// Stored on this node, no other agent reported getting it yet.
+    Committed(ActionHash),
+    /// Another agent got the message from the DHT.
+    Seen(ActionHash),
+    Failed(String),
+}
+
+impl DeliveryStatus {
+    /// Hash of the message once it is stored.
+    pub fn action_hash(&self) -> Option<&ActionHash> {
+        match self {
+            DeliveryStatus::Committed(action_hash) | DeliveryStatus::Seen(action_hash) => {
+                Some(action_hash)
+            }
+            DeliveryStatus::Pending | DeliveryStatus::Failed(_) => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OutgoingMessage {
+    pub id: u64,
+    pub text: String,
+    pub encrypt: bool,
+    /// File name and hash of the attachment.
+    pub attachment: Option<(String, EntryHash)>,
+    pub expires_at: Option<Timestamp>,
+    pub sent_at: Timestamp,
+    pub status: DeliveryStatus,
+    /// Delivery checks that found no other agent with the message.
+    delivery_checks: u32,
+    next_delivery_check: Timestamp,
+}
+
+impl OutgoingMessage {
+    /// Hash of the message if it's stored and its delivery is still checked.
+    fn awaiting_peers(&self) -> Option<&ActionHash> {
+        match &self.status {
+            DeliveryStatus::Committed(action_hash)
+                if self.delivery_checks < MAX_DELIVERY_CHECKS =>
+            {
+                Some(action_hash)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Messages sent in this session in the order they were sent, to show their
+/// delivery status. Failed messages leave the outbox when discarded.
+#[derive(Clone, Debug, Default)]
+pub struct Outbox {
+    messages: Vec<OutgoingMessage>,
+    next_id: u64,
+}
+
+impl Outbox {
+    pub fn push(
+        &mut self,
+        text: String,
+        encrypt: bool,
+        attachment: Option<(String, EntryHash)>,
+        expires_at: Option<Timestamp>,
+        sent_at: Timestamp,
+    ) -> &OutgoingMessage {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.messages.push(OutgoingMessage {
+            id,
+            text,
+            encrypt,
+            attachment,
+            expires_at,
+            sent_at,
+            status: DeliveryStatus::Pending,
+            delivery_checks: 0,
+            next_delivery_check: later(sent_at, FIRST_DELIVERY_CHECK),
+        });
+        &self.messages[self.messages.len() - 1]
+    }
+
+    pub fn get(&self, id: u64) -> Option<&OutgoingMessage> {
+        self.messages.iter().find(|message| message.id == id)
+    }
+
+    fn get_mut(&mut self, id: u64) -> Option<&mut OutgoingMessage> {
+        self.messages.iter_mut().find(|message| message.id == id)
+    }
+
+    pub fn set_status(&mut self, id: u64, status: DeliveryStatus) {
+        if let Some(message) = self.get_mut(id) {
+            message.status = status;
+        }
+    }
+
+    /// Make a failed message pending again, returning it to be sent.
+    pub fn retry(&mut self, id: u64) -> Option<&OutgoingMessage> {
+        let message = self
+            .get_mut(id)
+            .filter(|message| matches!(message.status, DeliveryStatus::Failed(_)))?;
+        message.status = DeliveryStatus::Pending;
+        Some(&*message)
+    }
+
+    pub fn discard(&mut self, id: u64) -> Option<OutgoingMessage> {
+        let index = self.messages.iter().position(|message| message.id == id)?;
+        Some(self.messages.remove(index))
+    }
+
+    /// Delivery status of the stored message `action_hash`, if it was sent
+    /// in this session.
+    pub fn status_of(&self, action_hash: &ActionHash) -> Option<&DeliveryStatus> {
+        self.messages
+            .iter()
+            .map(|message| &message.status)
+            .find(|status| status.action_hash() == Some(action_hash))
+    }
+
+    /// Messages stored that no other agent reported getting yet, while their
+    /// delivery is checked.
+    pub fn awaiting_peers(&self) -> impl Iterator<Item = &ActionHash> {
+        self.messages
+            .iter()
+            .filter_map(OutgoingMessage::awaiting_peers)
+    }
+
+    /// Messages awaiting peers whose delivery is to be checked at `now`.
+    pub fn delivery_checks_due(&self, now: Timestamp) -> impl Iterator<Item = &ActionHash> {
+        self.messages
+            .iter()
+            .filter(move |message| message.next_delivery_check <= now)
+            .filter_map(OutgoingMessage::awaiting_peers)
+    }
+
+    /// Check again later whether another agent got a stored message, as none
+    /// reported it at `now`.
+    pub fn delivery_unconfirmed(&mut self, action_hash: &ActionHash, now: Timestamp) {
+        for message in self.messages.iter_mut() {
+            if message.status == DeliveryStatus::Committed(action_hash.clone()) {
+                message.delivery_checks += 1;
+                message.next_delivery_check = later(
+                    now,
+                    FIRST_DELIVERY_CHECK * 2u32.saturating_pow(message.delivery_checks),
+                );
+            }
+        }
+    }
+
+    /// Mark a stored message as gotten by another agent.
+    pub fn mark_seen(&mut self, action_hash: &ActionHash) {
+        for message in self.messages.iter_mut() {
+            if message.status == DeliveryStatus::Committed(action_hash.clone()) {
+                message.status = DeliveryStatus::Seen(action_hash.clone());
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Outgoing messages oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &OutgoingMessage> {
+        self.messages.iter()
+    }
+}
+
+fn later(timestamp: Timestamp, duration: Duration) -> Timestamp {
+    (timestamp + duration).unwrap_or(Timestamp::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DeliveryStatus, Outbox, MAX_DELIVERY_CHECKS};
+    use hdk::prelude::{ActionHash, Timestamp};
+
+    #[test]
+    fn outgoing_message_lifecycle() {
+        let mut outbox = Outbox::default();
+        let first = outbox
+            .push(
+                "hi".to_string(),
+                false,
+                None,
+                None,
+                Timestamp::from_micros(1),
+            )
+            .id;
+        let second = outbox
+            .push(
+                "there".to_string(),
+                false,
+                None,
+                None,
+                Timestamp::from_micros(2),
+            )
+            .id;
+        assert_eq!(outbox.get(first).unwrap().status, DeliveryStatus::Pending);
+
+        // only failed messages are retried
+        assert!(outbox.retry(first).is_none());
+        outbox.set_status(second, DeliveryStatus::Failed("rate limit".to_string()));
+        assert_eq!(outbox.retry(second).unwrap().text, "there");
+        assert_eq!(outbox.get(second).unwrap().status, DeliveryStatus::Pending);
+
+        // stored messages stay committed until another agent got them
+        let action_hash = ActionHash::from_raw_36(vec![1; 36]);
+        outbox.set_status(first, DeliveryStatus::Committed(action_hash.clone()));
+        assert_eq!(
+            outbox.awaiting_peers().collect::<Vec<_>>(),
+            vec![&action_hash]
+        );
+        outbox.mark_seen(&ActionHash::from_raw_36(vec![2; 36]));
+        assert_eq!(
+            outbox.status_of(&action_hash),
+            Some(&DeliveryStatus::Committed(action_hash.clone()))
+        );
+        outbox.mark_seen(&action_hash);
+        assert_eq!(
+            outbox.status_of(&action_hash),
+            Some(&DeliveryStatus::Seen(action_hash.clone()))
+        );
+        assert_eq!(outbox.awaiting_peers().count(), 0);
+
+        // the delivery is checked less and less often, up to a limit
+        let action_hash = ActionHash::from_raw_36(vec![3; 36]);
+        outbox.set_status(second, DeliveryStatus::Committed(action_hash.clone()));
+        assert_eq!(
+            outbox
+                .delivery_checks_due(Timestamp::from_micros(2))
+                .count(),
+            0
+        );
+        let mut now = Timestamp::from_micros(5_000_002);
+        for checks in 0..MAX_DELIVERY_CHECKS {
+            assert_eq!(
+                outbox.delivery_checks_due(now).collect::<Vec<_>>(),
+                vec![&action_hash]
+            );
+            outbox.delivery_unconfirmed(&action_hash, now);
+            let next_check = 5_000_000 * 2i64.pow(checks + 1);
+            now = Timestamp::from_micros(now.as_micros() + next_check - 1);
+            assert_eq!(outbox.delivery_checks_due(now).count(), 0);
+            now = Timestamp::from_micros(now.as_micros() + 1);
+        }
+        assert_eq!(outbox.delivery_checks_due(now).count(), 0);
+        assert_eq!(outbox.awaiting_peers().count(), 0);
+        assert_eq!(
+            outbox.status_of(&action_hash),
+            Some(&DeliveryStatus::Committed(action_hash.clone()))
+        );
+
+        assert_eq!(outbox.discard(second).unwrap().text, "there");
+        let texts: Vec<_> = outbox.iter().map(|message| message.text.as_str()).collect();
+        assert_eq!(texts, vec!["hi"]);
+    }
+}
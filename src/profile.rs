@@ -0,0 +1,200 @@
+//! Profile details kept in `Profile.fields` next to the nickname.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use hc_zome_profiles_integrity::Profile;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
+    io::Cursor,
+};
+
+/// Profile field holding a PNG avatar, base64 encoded.
+pub const AVATAR_FIELD: &str = "avatar";
+pub const BIO_FIELD: &str = "bio";
+/// Profile field holding the display color as `#rrggbb`.
+pub const COLOR_FIELD: &str = "color";
+/// Edge length avatars are downscaled to before they are stored.
+pub const AVATAR_SIZE: u32 = 96;
+/// Maximum length of a bio in bytes.
+pub const MAX_BIO_LENGTH: usize = 500;
+/// Minimum length of a nickname in bytes, as validated by the profiles zome.
+pub const MIN_NICKNAME_LENGTH: usize = 3;
+/// Number of alternatives offered for a taken nickname.
+const NICKNAME_SUGGESTIONS: usize = 3;
+
+/// Why a nickname can't be used for a new profile.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NicknameProblem {
+    TooShort,
+    /// Another agent has the nickname, regardless of case.
+    Taken {
+        suggestions: Vec<String>,
+    },
+}
+
+impl Display for NicknameProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NicknameProblem::TooShort => write!(
+                f,
+                "Nicknames need at least {MIN_NICKNAME_LENGTH} characters"
+            ),
+            NicknameProblem::Taken { .. } => write!(f, "This nickname is taken"),
+        }
+    }
+}
+
+/// The editable details of a profile besides the nickname.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProfileDetails {
+    pub bio: String,
+    pub color: Option<[u8; 3]>,
+    /// Downscaled PNG.
+    pub avatar: Option<Vec<u8>>,
+}
+
+impl ProfileDetails {
+    pub fn of(profile: &Profile) -> Self {
+        ProfileDetails {
+            bio: profile.fields.get(BIO_FIELD).cloned().unwrap_or_default(),
+            color: profile
+                .fields
+                .get(COLOR_FIELD)
+                .and_then(|color| parse_color(color)),
+            avatar: avatar_of(profile),
+        }
+    }
+
+    /// Write the details into profile fields, keeping all other fields.
+    pub fn apply_to(&self, fields: &mut BTreeMap<String, String>) {
+        let mut set = |field: &str, value: Option<String>| match value {
+            Some(value) => {
+                fields.insert(field.to_string(), value);
+            }
+            None => {
+                fields.remove(field);
+            }
+        };
+        set(
+            BIO_FIELD,
+            Some(self.bio.trim().to_string()).filter(|bio| !bio.is_empty()),
+        );
+        set(COLOR_FIELD, self.color.map(format_color));
+        set(
+            AVATAR_FIELD,
+            self.avatar.as_ref().map(|avatar| BASE64.encode(avatar)),
+        );
+    }
+}
+
+pub fn avatar_of(profile: &Profile) -> Option<Vec<u8>> {
+    BASE64.decode(profile.fields.get(AVATAR_FIELD)?).ok()
+}
+
+/// Parse a color written as `#rrggbb`.
+pub fn parse_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.trim().strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+pub fn format_color([r, g, b]: [u8; 3]) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// Downscale an image to fit `AVATAR_SIZE` and encode it as PNG.
+pub fn downscale_avatar(image_bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let image = image::load_from_memory(image_bytes)
+        .map_err(|err| format!("could not read image: {:?}", err))?;
+    let mut png = Vec::new();
+    image
+        .thumbnail(AVATAR_SIZE, AVATAR_SIZE)
+        .write_to(&mut Cursor::new(&mut png), image::ImageOutputFormat::Png)
+        .map_err(|err| format!("could not encode avatar: {:?}", err))?;
+    Ok(png)
+}
+
+/// Problem with the nickname that can be told without asking the network.
+pub fn check_nickname_length(nickname: &str) -> Option<NicknameProblem> {
+    (nickname.trim().len() < MIN_NICKNAME_LENGTH).then_some(NicknameProblem::TooShort)
+}
+
+/// Variations of a taken nickname that are not in `taken`, which holds
+/// lowercase nicknames.
+pub fn suggest_nicknames(nickname: &str, taken: &HashSet<String>) -> Vec<String> {
+    let nickname = nickname.trim();
+    (2..)
+        .map(|number| format!("{nickname}{number}"))
+        .filter(|candidate| !taken.contains(&candidate.to_lowercase()))
+        .take(NICKNAME_SUGGESTIONS)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        check_nickname_length, downscale_avatar, parse_color, suggest_nicknames, NicknameProblem,
+        ProfileDetails, AVATAR_SIZE,
+    };
+    use hc_zome_profiles_integrity::Profile;
+    use image::{GenericImageView, ImageOutputFormat, RgbImage};
+    use std::{
+        collections::{BTreeMap, HashSet},
+        io::Cursor,
+    };
+
+    #[test]
+    fn details_round_trip_through_fields() {
+        let mut source = Vec::new();
+        image::DynamicImage::ImageRgb8(RgbImage::new(400, 200))
+            .write_to(&mut Cursor::new(&mut source), ImageOutputFormat::Png)
+            .unwrap();
+        let avatar = downscale_avatar(&source).unwrap();
+        let dimensions = image::load_from_memory(&avatar).unwrap().dimensions();
+        assert_eq!(dimensions, (AVATAR_SIZE, AVATAR_SIZE / 2));
+
+        let details = ProfileDetails {
+            bio: "  hi there ".to_string(),
+            color: parse_color("#1a2B3c"),
+            avatar: Some(avatar),
+        };
+        let mut fields = BTreeMap::new();
+        fields.insert("encryption_key".to_string(), "key".to_string());
+        details.apply_to(&mut fields);
+        let profile = Profile {
+            nickname: "alice".to_string(),
+            fields,
+        };
+        assert_eq!(profile.fields["encryption_key"], "key");
+        assert_eq!(profile.fields["color"], "#1a2b3c");
+        assert_eq!(
+            ProfileDetails::of(&profile),
+            ProfileDetails {
+                bio: "hi there".to_string(),
+                ..details
+            }
+        );
+        assert_eq!(parse_color("1a2b3c"), None);
+    }
+
+    #[test]
+    fn nickname_checks() {
+        assert_eq!(
+            check_nickname_length(" al "),
+            Some(NicknameProblem::TooShort)
+        );
+        assert_eq!(check_nickname_length("ali"), None);
+
+        let taken: HashSet<String> = ["alice", "alice2", "alice4"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(
+            suggest_nicknames("Alice", &taken),
+            vec!["Alice3", "Alice5", "Alice6"]
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use hdk::prelude::{ActionHash, ActionHashB64, AgentPubKey, AgentPubKeyB64, Timestamp};
+use std::{collections::HashSet, ops::Bound, path::Path, sync::Mutex};
+use tantivy::{
+    collector::TopDocs,
+    directory::MmapDirectory,
+    doc,
+    query::{AllQuery, BooleanQuery, Occur, Query, QueryParser, RangeQuery, TermQuery},
+    schema::{Field, IndexRecordOption, Schema, Value, FAST, INDEXED, STORED, STRING, TEXT},
+    Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, Term,
+};
+
+const SEARCH_INDEX_DIR: &str = "search-index";
+const WRITER_MEMORY_BUDGET: usize = 15_000_000;
+const MAX_SEARCH_RESULTS: usize = 50;
+
+/// A message to be added to the search index.
+#[derive(Clone, Debug)]
+pub struct IndexedMessage {
+    pub action_hash: ActionHash,
+    pub author: AgentPubKey,
+    pub timestamp: Timestamp,
+    pub text: String,
+    /// The other agent, if this is a direct message.
+    pub peer: Option<AgentPubKey>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SearchQuery {
+    pub text: String,
+    pub author: Option<AgentPubKey>,
+    pub since: Option<Timestamp>,
+    pub until: Option<Timestamp>,
+}
+
+pub type SearchHit = IndexedMessage;
+
+/// Full-text index of all readable messages this agent has seen, stored in
+/// the conductor directory.
+pub struct SearchIndex {
+    reader: IndexReader,
+    writer: Mutex<IndexWriter>,
+    query_parser: QueryParser,
+    fields: SearchFields,
+    indexed: Mutex<HashSet<ActionHash>>,
+}
+
+struct SearchFields {
+    action_hash: Field,
+    author: Field,
+    timestamp: Field,
+    text: Field,
+    peer: Field,
+}
+
+impl SearchIndex {
+    pub fn open(holochain_dir: &Path) -> Result<Self, String> {
+        let mut schema_builder = Schema::builder();
+        let fields = SearchFields {
+            action_hash: schema_builder.add_text_field("action_hash", STRING | STORED),
+            author: schema_builder.add_text_field("author", STRING | STORED),
+            timestamp: schema_builder.add_i64_field("timestamp", INDEXED | STORED | FAST),
+            text: schema_builder.add_text_field("text", TEXT | STORED),
+            peer: schema_builder.add_text_field("peer", STRING | STORED),
+        };
+        let schema = schema_builder.build();
+
+        let index_dir = holochain_dir.join(SEARCH_INDEX_DIR);
+        std::fs::create_dir_all(&index_dir)
+            .map_err(|err| format!("could not create search index directory: {:?}", err))?;
+        let directory = MmapDirectory::open(&index_dir)
+            .map_err(|err| format!("could not open search index directory: {:?}", err))?;
+        let index = Index::open_or_create(directory, schema)
+            .map_err(|err| format!("could not open search index: {:?}", err))?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()
+            .map_err(|err| format!("could not create search index reader: {:?}", err))?;
+        let writer = index
+            .writer_with_num_threads(1, WRITER_MEMORY_BUDGET)
+            .map_err(|err| format!("could not create search index writer: {:?}", err))?;
+        let query_parser = QueryParser::for_index(&index, vec![fields.text]);
+
+        Ok(SearchIndex {
+            reader,
+            writer: Mutex::new(writer),
+            query_parser,
+            fields,
+            indexed: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// Add messages that haven't been indexed during this session. Messages
+    /// indexed in an earlier session are replaced.
+    pub fn add_messages(&self, messages: Vec<IndexedMessage>) -> Result<(), String> {
+        let mut indexed = self.indexed.lock().unwrap();
+        let messages: Vec<IndexedMessage> = messages
+            .into_iter()
+            .filter(|message| !indexed.contains(&message.action_hash))
+            .collect();
+        if messages.is_empty() {
+            return Ok(());
+        }
+
+        let mut writer = self.writer.lock().unwrap();
+        for message in messages.iter() {
+            let action_hash = ActionHashB64::from(message.action_hash.clone()).to_string();
+            writer.delete_term(Term::from_field_text(self.fields.action_hash, &action_hash));
+            writer
+                .add_document(doc!(
+                    self.fields.action_hash => action_hash,
+                    self.fields.author => AgentPubKeyB64::from(message.author.clone()).to_string(),
+                    self.fields.timestamp => message.timestamp.as_micros(),
+                    self.fields.text => message.text.clone(),
+                    self.fields.peer => message
+                        .peer
+                        .clone()
+                        .map(|peer| AgentPubKeyB64::from(peer).to_string())
+                        .unwrap_or_default(),
+                ))
+                .map_err(|err| format!("could not add message to search index: {:?}", err))?;
+        }
+        writer
+            .commit()
+            .map_err(|err| format!("could not commit search index: {:?}", err))?;
+        self.reader
+            .reload()
+            .map_err(|err| format!("could not reload search index: {:?}", err))?;
+
+        indexed.extend(messages.into_iter().map(|message| message.action_hash));
+        Ok(())
+    }
+
+    /// Messages matching the query, best match first.
+    pub fn search(&self, query: &SearchQuery) -> Result<Vec<SearchHit>, String> {
+        let mut subqueries: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        if query.text.trim().is_empty() {
+            subqueries.push((Occur::Must, Box::new(AllQuery)));
+        } else {
+            let (text_query, errors) = self.query_parser.parse_query_lenient(&query.text);
+            if !errors.is_empty() {
+                println!(
+                    "search query {:?} partially parsed: {:?}",
+                    query.text, errors
+                );
+            }
+            subqueries.push((Occur::Must, text_query));
+        }
+        if let Some(author) = &query.author {
+            let author = AgentPubKeyB64::from(author.clone()).to_string();
+            subqueries.push((
+                Occur::Must,
+                Box::new(TermQuery::new(
+                    Term::from_field_text(self.fields.author, &author),
+                    IndexRecordOption::Basic,
+                )),
+            ));
+        }
+        if query.since.is_some() || query.until.is_some() {
+            let lower_bound = query
+                .since
+                .map_or(Bound::Unbounded, |since| Bound::Included(since.as_micros()));
+            let upper_bound = query
+                .until
+                .map_or(Bound::Unbounded, |until| Bound::Excluded(until.as_micros()));
+            subqueries.push((
+                Occur::Must,
+                Box::new(RangeQuery::new_i64_bounds(
+                    "timestamp".to_string(),
+                    lower_bound,
+                    upper_bound,
+                )),
+            ));
+        }
+
+        let searcher = self.reader.searcher();
+        let top_docs = searcher
+            .search(
+                &BooleanQuery::new(subqueries),
+                &TopDocs::with_limit(MAX_SEARCH_RESULTS),
+            )
+            .map_err(|err| format!("search failed: {:?}", err))?;
+
+        let mut hits = Vec::with_capacity(top_docs.len());
+        for (_score, doc_address) in top_docs {
+            let doc: TantivyDocument = searcher
+                .doc(doc_address)
+                .map_err(|err| format!("could not load search result: {:?}", err))?;
+            if let Some(hit) = self.hit_from_doc(&doc) {
+                hits.push(hit);
+            }
+        }
+        Ok(hits)
+    }
+
+    fn hit_from_doc(&self, doc: &TantivyDocument) -> Option<SearchHit> {
+        let text_of = |field| doc.get_first(field).and_then(|value| value.as_str());
+        let action_hash = ActionHashB64::from_b64_str(text_of(self.fields.action_hash)?).ok()?;
+        let author = AgentPubKeyB64::from_b64_str(text_of(self.fields.author)?).ok()?;
+        let peer = text_of(self.fields.peer)
+            .and_then(|peer| AgentPubKeyB64::from_b64_str(peer).ok())
+            .map(AgentPubKey::from);
+        let timestamp = doc
+            .get_first(self.fields.timestamp)
+            .and_then(|value| value.as_i64())?;
+        Some(SearchHit {
+            action_hash: action_hash.into(),
+            author: author.into(),
+            timestamp: Timestamp::from_micros(timestamp),
+            text: text_of(self.fields.text)?.to_string(),
+            peer,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IndexedMessage, SearchIndex, SearchQuery};
+    use hdk::prelude::{ActionHash, AgentPubKey, Timestamp};
+
+    #[test]
+    fn search_by_text_author_and_date() {
+        let dir = tempfile::tempdir().unwrap();
+        let index = SearchIndex::open(dir.path()).unwrap();
+        let alice = AgentPubKey::from_raw_36(vec![1; 36]);
+        let bob = AgentPubKey::from_raw_36(vec![2; 36]);
+        index
+            .add_messages(vec![
+                IndexedMessage {
+                    action_hash: ActionHash::from_raw_36(vec![1; 36]),
+                    author: alice.clone(),
+                    timestamp: Timestamp::from_micros(1_000),
+                    text: "hello holochain".to_string(),
+                    peer: None,
+                },
+                IndexedMessage {
+                    action_hash: ActionHash::from_raw_36(vec![2; 36]),
+                    author: bob.clone(),
+                    timestamp: Timestamp::from_micros(2_000),
+                    text: "hello iced".to_string(),
+                    peer: Some(alice.clone()),
+                },
+            ])
+            .unwrap();
+
+        let hits = index
+            .search(&SearchQuery {
+                text: "hello".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(hits.len(), 2);
+
+        let hits = index
+            .search(&SearchQuery {
+                text: "hello".to_string(),
+                author: Some(bob),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].peer, Some(alice));
+
+        let hits = index
+            .search(&SearchQuery {
+                text: String::new(),
+                until: Some(Timestamp::from_micros(2_000)),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].text, "hello holochain");
+    }
+}
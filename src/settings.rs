@@ -0,0 +1,178 @@
+//! User preferences, kept in the conductor directory next to
+//! `conductor-config.yaml`.
+
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, path::Path};
+
+pub const SETTINGS_FILE: &str = "settings.yaml";
+pub const MIN_FONT_SCALE: f32 = 0.75;
+pub const MAX_FONT_SCALE: f32 = 1.5;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct UserSettings {
+    pub theme: ThemeSetting,
+    /// Scale of text and the rest of the interface.
+    pub font_scale: f32,
+    pub timestamp_format: TimestampFormat,
+    /// Enter sends the message being written, otherwise only the send
+    /// button does.
+    pub enter_to_send: bool,
+    pub notifications: NotificationSettings,
+}
+
+impl Default for UserSettings {
+    fn default() -> Self {
+        UserSettings {
+            theme: ThemeSetting::default(),
+            font_scale: 1.0,
+            timestamp_format: TimestampFormat::default(),
+            enter_to_send: true,
+            notifications: NotificationSettings::default(),
+        }
+    }
+}
+
+impl UserSettings {
+    /// Settings stored in `dir`, or the defaults if none have been saved yet.
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(SETTINGS_FILE);
+        if !path.exists() {
+            return Ok(UserSettings::default());
+        }
+        let yaml = std::fs::read_to_string(&path)
+            .map_err(|err| format!("could not read settings: {:?}", err))?;
+        let settings: UserSettings = serde_yaml::from_str(&yaml)
+            .map_err(|err| format!("could not parse settings: {:?}", err))?;
+        Ok(settings.clamped())
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<(), String> {
+        let yaml = serde_yaml::to_string(self)
+            .map_err(|err| format!("could not serialize settings: {:?}", err))?;
+        std::fs::create_dir_all(dir)
+            .map_err(|err| format!("could not create settings directory: {:?}", err))?;
+        std::fs::write(dir.join(SETTINGS_FILE), yaml)
+            .map_err(|err| format!("could not write settings: {:?}", err))
+    }
+
+    /// Settings with values edited by hand brought into range.
+    fn clamped(mut self) -> Self {
+        self.font_scale = self.font_scale.clamp(MIN_FONT_SCALE, MAX_FONT_SCALE);
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeSetting {
+    #[default]
+    Light,
+    Dark,
+    Custom(CustomPalette),
+}
+
+/// Colors of a custom theme, written as `#rrggbb`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct CustomPalette {
+    pub background: String,
+    pub text: String,
+    pub primary: String,
+    pub success: String,
+    pub danger: String,
+}
+
+impl Default for CustomPalette {
+    fn default() -> Self {
+        CustomPalette {
+            background: "#fdf6e3".to_string(),
+            text: "#586e75".to_string(),
+            primary: "#268bd2".to_string(),
+            success: "#859900".to_string(),
+            danger: "#dc322f".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampFormat {
+    #[default]
+    TwentyFourHour,
+    TwelveHour,
+}
+
+impl TimestampFormat {
+    pub const ALL: [TimestampFormat; 2] =
+        [TimestampFormat::TwentyFourHour, TimestampFormat::TwelveHour];
+
+    /// `chrono` format of a time of day.
+    pub fn time_pattern(&self) -> &'static str {
+        match self {
+            TimestampFormat::TwentyFourHour => "%H:%M",
+            TimestampFormat::TwelveHour => "%-I:%M %p",
+        }
+    }
+}
+
+impl Display for TimestampFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimestampFormat::TwentyFourHour => write!(f, "24-hour (14:30)"),
+            TimestampFormat::TwelveHour => write!(f, "12-hour (2:30 PM)"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct NotificationSettings {
+    /// Number of unread messages in the window title.
+    pub unread_count_in_title: bool,
+    /// Show when other agents are typing.
+    pub typing_indicators: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        NotificationSettings {
+            unread_count_in_title: true,
+            typing_indicators: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CustomPalette, ThemeSetting, TimestampFormat, UserSettings, MAX_FONT_SCALE};
+
+    #[test]
+    fn settings_round_trip_through_file() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            UserSettings::load(dir.path()).unwrap(),
+            UserSettings::default()
+        );
+
+        let settings = UserSettings {
+            theme: ThemeSetting::Custom(CustomPalette::default()),
+            font_scale: 1.25,
+            timestamp_format: TimestampFormat::TwelveHour,
+            enter_to_send: false,
+            ..Default::default()
+        };
+        settings.save(dir.path()).unwrap();
+        assert_eq!(UserSettings::load(dir.path()).unwrap(), settings);
+
+        // missing fields get their defaults, values out of range are clamped
+        std::fs::write(dir.path().join("settings.yaml"), "font_scale: 4.0\n").unwrap();
+        assert_eq!(
+            UserSettings::load(dir.path()).unwrap(),
+            UserSettings {
+                font_scale: MAX_FONT_SCALE,
+                ..Default::default()
+            }
+        );
+    }
+}